@@ -0,0 +1,104 @@
+//! Policy evaluation over a tools/list-sized catalog: the per-call glob scan of
+//! an uncompiled `Policy` against the indexed lookup of a compiled one, with
+//! and without argument conditions and caller patterns.
+//!
+//! Run with `cargo bench --bench policy`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use neurond::security::identity::CallerIdentity;
+use neurond::security::policy::Policy;
+
+const NAMESPACES: usize = 20;
+const GROUPS: usize = 5;
+const TOOLS_PER_GROUP: usize = 6;
+
+/// 600 tools named `ns<n>.group<g>.tool<t>`.
+fn catalog() -> Vec<String> {
+    let mut tools = Vec::new();
+    for ns in 0..NAMESPACES {
+        for group in 0..GROUPS {
+            for tool in 0..TOOLS_PER_GROUP {
+                tools.push(format!("ns{}.group{}.tool{}", ns, group, tool));
+            }
+        }
+    }
+    tools
+}
+
+/// About 300 rules mixing exact names, `prefix*` patterns and other globs.
+/// With `scoped`, each namespace also gets rules with argument conditions and callers.
+fn policy(scoped: bool) -> Policy {
+    let mut toml = String::from("default_action = \"deny\"\n");
+    for ns in 0..NAMESPACES {
+        for group in 0..GROUPS {
+            toml.push_str(&format!(
+                "[[rules]]\nid = \"allow-ns{ns}-group{group}\"\neffect = \"allow\"\ntools = [\"ns{ns}.group{group}.*\"]\n"
+            ));
+        }
+        for tool in 0..TOOLS_PER_GROUP {
+            toml.push_str(&format!(
+                "[[rules]]\nid = \"deny-ns{ns}-tool{tool}\"\neffect = \"deny\"\ntools = [\"ns{ns}.group0.tool{tool}\", \"ns{ns}.*.tool{tool}x\"]\n"
+            ));
+        }
+        toml.push_str(&format!(
+            "[[rules]]\nid = \"approve-ns{ns}\"\neffect = \"require_approval\"\ntools = [\"ns{ns}.group?.tool5\"]\n"
+        ));
+        if scoped {
+            toml.push_str(&format!(
+                "[[rules]]\nid = \"sre-ns{ns}\"\neffect = \"allow\"\ntools = [\"ns{ns}.group1.*\"]\n\
+                 conditions = [{{ pointer = \"/unit\", glob = \"*.service\" }}, {{ pointer = \"/unit\", regex = '(nginx|redis)-[0-9]+\\.service' }}]\n\
+                 callers = [{{ token_id = \"ops-*\" }}, {{ claims = {{ groups = \"sre-*\" }} }}]\n"
+            ));
+            toml.push_str(&format!(
+                "[[rules]]\nid = \"staging-ns{ns}\"\neffect = \"deny\"\ntools = [\"ns{ns}.*\"]\n\
+                 conditions = [{{ pointer = \"/path\", path_prefix = \"/etc\" }}]\n\
+                 callers = [{{ client_name = \"staging-*\", cert_subject = \"CN=staging*\" }}]\n"
+            ));
+        }
+    }
+    toml::from_str(&toml).unwrap()
+}
+
+fn is_allowed(c: &mut Criterion) {
+    let tools = catalog();
+    let scan = policy(false);
+    let compiled = policy(false).compile();
+    let caller = CallerIdentity::default();
+    let args = serde_json::json!({});
+
+    let mut group = c.benchmark_group("is_allowed/600 tools");
+    group.bench_function("uncompiled", |b| {
+        b.iter(|| tools.iter().filter(|tool| scan.is_allowed(black_box(tool), &args, &caller)).count())
+    });
+    group.bench_function("compiled", |b| {
+        b.iter(|| tools.iter().filter(|tool| compiled.is_allowed(black_box(tool), &args, &caller)).count())
+    });
+    group.finish();
+}
+
+fn is_allowed_scoped(c: &mut Criterion) {
+    let tools = catalog();
+    let scan = policy(true);
+    let compiled = policy(true).compile();
+    let mut caller = CallerIdentity {
+        client_name: Some("staging-agent".into()),
+        cert_subject: Some("CN=staging-7".into()),
+        ..Default::default()
+    };
+    caller.claims.insert("groups".into(), serde_json::json!(["dev", "sre-oncall"]));
+    let args = serde_json::json!({"unit": "nginx-1.service", "path": "/var/log/nginx/../syslog"});
+
+    let mut group = c.benchmark_group("is_allowed/600 tools, conditions and callers");
+    group.bench_function("uncompiled", |b| {
+        b.iter(|| tools.iter().filter(|tool| scan.is_allowed(black_box(tool), &args, &caller)).count())
+    });
+    group.bench_function("compiled", |b| {
+        b.iter(|| tools.iter().filter(|tool| compiled.is_allowed(black_box(tool), &args, &caller)).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, is_allowed, is_allowed_scoped);
+criterion_main!(benches);
@@ -0,0 +1,336 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Path, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ring::digest;
+use rmcp::model::Tool;
+use serde::Deserialize;
+
+use crate::config::AdminConfig;
+use crate::federation::manager::{DownstreamStatus, FederationManager};
+use crate::reload;
+use crate::security::approval::{ApprovalDecision, ApprovalQueue, PendingCall};
+use crate::security::audit::AuditLogger;
+use crate::security::policy::PolicyStore;
+
+/// Shared state of the operator endpoints.
+#[derive(Clone)]
+pub struct AdminState {
+    pub policy_path: String,
+    pub policy: PolicyStore,
+    pub audit: Arc<AuditLogger>,
+    pub approvals: Arc<ApprovalQueue>,
+    pub federation: Arc<FederationManager>,
+}
+
+/// Serve the operator endpoints under `/api/v1/admin` on the listener of
+/// `config`, apart from the MCP endpoint. Returns once the listener is bound.
+///
+/// Without a `token_file` the endpoints are not served at all.
+pub async fn spawn(config: &AdminConfig, state: AdminState) -> anyhow::Result<()> {
+    let Some(token_file) = &config.token_file else {
+        tracing::warn!("No [server.admin] token_file configured — admin API disabled");
+        return Ok(());
+    };
+    let token = std::fs::read_to_string(token_file)
+        .with_context(|| format!("Failed to read admin token file: {}", token_file))?;
+    let app = Router::new().nest("/api/v1/admin", router(state, token.trim()));
+
+    if let Some(socket) = &config.socket {
+        let listener = bind_private_socket(std::path::Path::new(socket))
+            .with_context(|| format!("Failed to bind admin socket: {}", socket))?;
+        tracing::info!("Admin API listening on unix:{}", socket);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+    } else {
+        let listener = tokio::net::TcpListener::bind(&config.bind)
+            .await
+            .with_context(|| format!("Failed to bind admin listener: {}", config.bind))?;
+        tracing::info!("Admin API listening on http://{}", config.bind);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+    }
+    Ok(())
+}
+
+/// Bind a Unix socket at `path` that only our user can connect to.
+///
+/// The socket is bound in a private 0700 directory next to `path`, set to 0600
+/// and only then moved into place, so it is never reachable with looser permissions.
+fn bind_private_socket(path: &std::path::Path) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+    let staging = parent.join(format!(".admin-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    let bind = || -> anyhow::Result<tokio::net::UnixListener> {
+        let staged = staging.join("s");
+        let listener = tokio::net::UnixListener::bind(&staged)?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        let _ = std::fs::remove_file(path);
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    };
+    let listener = bind();
+    let _ = std::fs::remove_dir_all(&staging);
+    listener
+}
+
+/// Operator endpoints; every request must carry `Authorization: Bearer <token>`.
+pub fn router(state: AdminState, token: &str) -> Router {
+    let token_hash = Arc::new(sha256(token));
+    Router::new()
+        .route("/policy/reload", post(reload_policy))
+        .route("/tools", get(list_tools))
+        .route("/status", get(status))
+        .route("/approvals", get(list_approvals))
+        .route("/approvals/{id}/approve", post(approve))
+        .route("/approvals/{id}/reject", post(reject))
+        .layer(middleware::from_fn_with_state(token_hash, require_token))
+        .with_state(state)
+}
+
+/// Answer 401 unless the request carries the admin bearer token.
+async fn require_token(State(token_hash): State<Arc<Vec<u8>>>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| sha256(token.trim()) == *token_hash);
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+fn sha256(value: &str) -> Vec<u8> {
+    digest::digest(&digest::SHA256, value.as_bytes()).as_ref().to_vec()
+}
+
+/// `POST /policy/reload` — re-read the policy file and swap it in.
+///
+/// Answers 422 with the parse error if the file is invalid; the old policy stays in effect.
+async fn reload_policy(State(state): State<AdminState>) -> (StatusCode, Json<serde_json::Value>) {
+    match reload::reload_policy(&state.policy_path, &state.policy, &state.audit, &state.federation).await {
+        Ok(reload) => (StatusCode::OK, Json(serde_json::json!(reload))),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({ "error": e })),
+        ),
+    }
+}
+
+/// `GET /tools` — the full federated tool catalog, unfiltered by policy,
+/// for `neurond policy lint --catalog` and `neurond policy simulate`.
+async fn list_tools(State(state): State<AdminState>) -> Json<Vec<Tool>> {
+    Json(state.federation.list_all_tools().await)
+}
+
+/// `GET /status` — state of every downstream, with the stderr tail of stdio children.
+async fn status(State(state): State<AdminState>) -> Json<Vec<DownstreamStatus>> {
+    Json(state.federation.status().await)
+}
+
+/// `GET /approvals` — tool calls waiting for a decision, oldest first.
+async fn list_approvals(State(state): State<AdminState>) -> Json<Vec<PendingCall>> {
+    Json(state.approvals.pending())
+}
+
+/// Optional body of the approve and reject endpoints.
+#[derive(Debug, Default, Deserialize)]
+struct DecisionBody {
+    /// Name of the approver, recorded in the audit log (default "admin")
+    by: Option<String>,
+    reason: Option<String>,
+}
+
+/// `POST /approvals/{id}/approve`
+async fn approve(
+    state: State<AdminState>,
+    id: Path<String>,
+    body: Option<Json<DecisionBody>>,
+) -> StatusCode {
+    decide(state, id, body, true)
+}
+
+/// `POST /approvals/{id}/reject`
+async fn reject(
+    state: State<AdminState>,
+    id: Path<String>,
+    body: Option<Json<DecisionBody>>,
+) -> StatusCode {
+    decide(state, id, body, false)
+}
+
+/// 204 once the waiting call is released, 404 if it is unknown, expired or already decided.
+fn decide(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    body: Option<Json<DecisionBody>>,
+    approved: bool,
+) -> StatusCode {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let decision = ApprovalDecision {
+        approved,
+        by: body.by.unwrap_or_else(|| "admin".to_string()),
+        reason: body.reason,
+    };
+    if state.approvals.resolve(&id, decision) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::policy::Policy;
+
+    const TOKEN: &str = "admin-secret";
+
+    fn authorized_client() -> reqwest::Client {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(AUTHORIZATION, format!("Bearer {TOKEN}").parse().unwrap());
+        reqwest::Client::builder().default_headers(headers).build().unwrap()
+    }
+
+    fn test_state() -> AdminState {
+        AdminState {
+            policy_path: "policy.toml".into(),
+            policy: PolicyStore::from(Policy::default()),
+            audit: Arc::new(AuditLogger::new("ignore.log")),
+            approvals: Arc::new(ApprovalQueue::default()),
+            federation: Arc::new(FederationManager::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_socket_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("neurond-admin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("admin.sock");
+        std::fs::write(&socket, "stale").unwrap();
+
+        let _listener = bind_private_socket(&socket).unwrap();
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        tokio::net::UnixStream::connect(&socket).await.unwrap();
+        // Only the socket is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_require_token() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/api/v1/admin", listener.local_addr().unwrap());
+        let app = Router::new().nest("/api/v1/admin", router(test_state(), TOKEN));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let http = reqwest::Client::new();
+
+        let response = http.get(format!("{base}/status")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = http.get(format!("{base}/status")).bearer_auth("wrong").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = http.post(format!("{base}/policy/reload")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = authorized_client().get(format!("{base}/status")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_policy_reload_endpoint() {
+        let dir = std::env::temp_dir().join(format!("neurond-admin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy_path = dir.join("policy.toml").to_str().unwrap().to_string();
+        let audit = AuditLogger::new(dir.join("audit.log").to_str().unwrap());
+        let store = PolicyStore::from(Policy::default());
+        let state = AdminState {
+            policy_path: policy_path.clone(),
+            policy: store.clone(),
+            audit: Arc::new(audit),
+            approvals: Arc::new(ApprovalQueue::default()),
+            federation: Arc::new(FederationManager::new()),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/v1/admin/policy/reload", listener.local_addr().unwrap());
+        let app = Router::new().nest("/api/v1/admin", router(state, TOKEN));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let http = authorized_client();
+
+        std::fs::write(&policy_path, r#"default_action = "allow""#).unwrap();
+        let response = http.post(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["old_hash"], serde_json::Value::Null);
+        assert_eq!(body["new_hash"], store.hash().unwrap());
+        assert!(store.load().is_allowed("linux.system.cpu", &serde_json::json!({}), &Default::default()));
+
+        std::fs::write(&policy_path, "default_action = ").unwrap();
+        let response = http.post(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(store.load().is_allowed("linux.system.cpu", &serde_json::json!({}), &Default::default()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_approval_endpoints() {
+        let state = test_state();
+        let approvals = state.approvals.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/api/v1/admin", listener.local_addr().unwrap());
+        let app = Router::new().nest("/api/v1/admin", router(state, TOKEN));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let http = authorized_client();
+
+        let waiter = tokio::spawn(async move {
+            let audit = AuditLogger::new("ignore.log");
+            let caller = Default::default();
+            approvals
+                .request("linux.service.restart", &serde_json::json!({}), &caller, None, &audit)
+                .await
+        });
+        let pending = loop {
+            let pending: Vec<serde_json::Value> =
+                http.get(format!("{base}/approvals")).send().await.unwrap().json().await.unwrap();
+            if !pending.is_empty() {
+                break pending;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(pending[0]["tool"], "linux.service.restart");
+        let id = pending[0]["id"].as_str().unwrap();
+
+        let response = http
+            .post(format!("{base}/approvals/{id}/reject"))
+            .json(&serde_json::json!({ "by": "alice", "reason": "not during business hours" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            waiter.await.unwrap(),
+            crate::security::approval::ApprovalOutcome::Decided(ApprovalDecision {
+                approved: false,
+                by: "alice".into(),
+                reason: Some("not during business hours".into()),
+            })
+        );
+
+        // Already decided
+        let response = http.post(format!("{base}/approvals/{id}/approve")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::security::identity::CallerIdentity;
+use crate::security::lint::{self, Severity};
+use crate::security::policy::{Effect, Policy, PolicyDecision};
+
+const USAGE: &str = "\
+Usage:
+  neurond policy lint <policy.toml> [--catalog <tools.json>]
+  neurond policy test <policy.toml> <cases.toml>
+  neurond policy simulate <policy.toml> --catalog <tools.json> [--caller <caller.json>]
+
+<tools.json> is the tool catalog of a running neurond, as served by
+GET /api/v1/admin/tools.";
+
+/// Run `neurond policy ...` and return the exit code: 0 on success, 1 if the
+/// policy has errors or failing test cases, 2 on bad usage or unreadable input.
+pub fn run_policy(args: &[String]) -> i32 {
+    match policy_command(args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            2
+        }
+    }
+}
+
+fn policy_command(args: &[String]) -> anyhow::Result<bool> {
+    let mut positional = Vec::new();
+    let mut catalog = None;
+    let mut caller = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--catalog" => catalog = Some(args.next().context("--catalog needs a file")?),
+            "--caller" => caller = Some(args.next().context("--caller needs a file")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(true);
+            }
+            flag if flag.starts_with('-') => bail!("unknown option {}\n\n{}", flag, USAGE),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    match positional.as_slice() {
+        ["lint", policy] => lint_policy(policy, catalog.map(String::as_str)),
+        ["test", policy, cases] => test_policy(policy, cases),
+        ["simulate", policy] => simulate(
+            policy,
+            catalog.context("simulate needs --catalog")?,
+            caller.map(String::as_str),
+        ),
+        _ => bail!("{}", USAGE),
+    }
+}
+
+/// `neurond policy lint`: parse errors and lint errors fail, warnings don't.
+fn lint_policy(policy_path: &str, catalog_path: Option<&str>) -> anyhow::Result<bool> {
+    let catalog = catalog_path.map(load_catalog).transpose()?;
+    let policy = match Policy::load_from_file(policy_path) {
+        Ok(policy) => policy,
+        Err(e) => {
+            println!("{}: error: {}", policy_path, e);
+            return Ok(false);
+        }
+    };
+
+    let findings = lint::lint(&policy, catalog.as_deref());
+    for finding in &findings {
+        let severity = match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("{}: rule {:?}: {}: {}", policy_path, finding.rule, severity, finding.message);
+    }
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    println!(
+        "{}: {} rules, {} errors, {} warnings",
+        policy_path,
+        policy.rules.len(),
+        errors,
+        findings.len() - errors
+    );
+    Ok(errors == 0)
+}
+
+/// Expected decisions for a policy, kept next to it as e.g. `policy.tests.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyTests {
+    #[serde(default)]
+    pub cases: Vec<PolicyCase>,
+}
+
+/// One request and the effect the policy must decide for it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyCase {
+    /// Shown when the case fails; defaults to the request
+    pub name: Option<String>,
+    /// Exactly one of `tool`, `resource` and `prompt` is set
+    pub tool: Option<String>,
+    pub resource: Option<String>,
+    pub prompt: Option<String>,
+    /// Tool call arguments
+    #[serde(default = "empty_arguments")]
+    pub arguments: Value,
+    #[serde(default)]
+    pub caller: CallerIdentity,
+    pub expect: Effect,
+}
+
+fn empty_arguments() -> Value {
+    Value::Object(Default::default())
+}
+
+/// `neurond policy test`: every case must get its expected effect.
+fn test_policy(policy_path: &str, cases_path: &str) -> anyhow::Result<bool> {
+    let policy = Policy::load_from_file(policy_path).map_err(anyhow::Error::msg)?;
+    let content = std::fs::read_to_string(cases_path)
+        .with_context(|| format!("Failed to read test cases: {}", cases_path))?;
+    let tests: PolicyTests =
+        toml::from_str(&content).with_context(|| format!("Failed to parse test cases: {}", cases_path))?;
+
+    let failures = run_cases(&policy, &tests)?;
+    for failure in &failures {
+        println!("FAIL {}", failure);
+    }
+    println!("{} passed, {} failed", tests.cases.len() - failures.len(), failures.len());
+    Ok(failures.is_empty())
+}
+
+/// Descriptions of the cases that did not get their expected effect.
+pub fn run_cases(policy: &Policy, tests: &PolicyTests) -> anyhow::Result<Vec<String>> {
+    let mut failures = Vec::new();
+    for (i, case) in tests.cases.iter().enumerate() {
+        let (request, decision) = match (&case.tool, &case.resource, &case.prompt) {
+            (Some(tool), None, None) => (
+                format!("tool {:?}", tool),
+                policy.decide_tool(tool, &case.arguments, &case.caller),
+            ),
+            (None, Some(uri), None) => (format!("resource {:?}", uri), policy.decide_resource(uri, &case.caller)),
+            (None, None, Some(prompt)) => (format!("prompt {:?}", prompt), policy.decide_prompt(prompt, &case.caller)),
+            _ => bail!("case {} must set exactly one of tool, resource and prompt", i + 1),
+        };
+        if decision.effect != case.expect {
+            failures.push(format!(
+                "{}: expected {}, got {} ({})",
+                case.name.as_deref().unwrap_or(&request),
+                effect_name(case.expect),
+                effect_name(decision.effect),
+                explain(&decision)
+            ));
+        }
+    }
+    Ok(failures)
+}
+
+/// `neurond policy simulate`: how a caller would see each tool in the catalog,
+/// called without arguments.
+fn simulate(policy_path: &str, catalog_path: &str, caller_path: Option<&str>) -> anyhow::Result<bool> {
+    let policy = Policy::load_from_file(policy_path).map_err(anyhow::Error::msg)?;
+    let catalog = load_catalog(catalog_path)?;
+    let caller: CallerIdentity = match caller_path {
+        Some(path) => {
+            let content =
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read caller: {}", path))?;
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse caller: {}", path))?
+        }
+        None => CallerIdentity::default(),
+    };
+
+    let width = catalog.iter().map(String::len).max().unwrap_or(0);
+    for tool in &catalog {
+        let decision = policy.decide_tool(tool, &empty_arguments(), &caller);
+        let visibility = if policy.is_tool_visible(tool, &caller) { "" } else { " (hidden)" };
+        let effect = format!("{}{}", effect_name(decision.effect), visibility);
+        println!("{:width$}  {:25}  {}", tool, effect, explain(&decision), width = width);
+    }
+    Ok(true)
+}
+
+/// Namespaced tool names of a catalog dump: a JSON array of tools or names,
+/// or a `tools/list` result with one under `tools`.
+pub fn load_catalog(path: &str) -> anyhow::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read catalog: {}", path))?;
+    let value: Value = serde_json::from_str(&content).with_context(|| format!("Failed to parse catalog: {}", path))?;
+    catalog_names(&value).with_context(|| format!("Unexpected catalog format: {}", path))
+}
+
+fn catalog_names(value: &Value) -> anyhow::Result<Vec<String>> {
+    let tools = match value.get("tools") {
+        Some(tools) => tools,
+        None => value,
+    };
+    let Some(tools) = tools.as_array() else {
+        bail!("expected an array of tools");
+    };
+    tools
+        .iter()
+        .map(|tool| {
+            tool.as_str()
+                .or_else(|| tool.get("name").and_then(Value::as_str))
+                .map(str::to_string)
+                .context("tool without a name")
+        })
+        .collect()
+}
+
+fn effect_name(effect: Effect) -> &'static str {
+    match effect {
+        Effect::Allow => "allow",
+        Effect::Deny => "deny",
+        Effect::RequireApproval => "require_approval",
+    }
+}
+
+/// The rules behind a decision, e.g. `rules "read-only", "no-shell"`.
+fn explain(decision: &PolicyDecision) -> String {
+    if decision.default_applied {
+        return "default_action".to_string();
+    }
+    let ids: Vec<String> = decision.matched.iter().map(|m| format!("{:?}", m.id)).collect();
+    format!("rules {}", ids.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_cases() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "read-only"
+            effect = "allow"
+            tools = ["linux.system.*", "linux.service.*"]
+            [[rules]]
+            id = "restarts"
+            effect = "require_approval"
+            tools = ["linux.service.restart"]
+            callers = [{ claims = { groups = "sre" } }]
+            "#,
+        )
+        .unwrap();
+        let tests: PolicyTests = toml::from_str(
+            r#"
+            [[cases]]
+            tool = "linux.system.cpu"
+            expect = "allow"
+
+            [[cases]]
+            name = "sre restarts need approval"
+            tool = "linux.service.restart"
+            caller = { claims = { groups = ["sre"] } }
+            expect = "require_approval"
+
+            [[cases]]
+            name = "others cannot restart"
+            tool = "linux.service.restart"
+            expect = "deny"
+
+            [[cases]]
+            resource = "linux.file:///etc/shadow"
+            expect = "deny"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            run_cases(&policy, &tests).unwrap(),
+            vec!["others cannot restart: expected deny, got allow (rules \"read-only\")".to_string()]
+        );
+
+        let ambiguous: PolicyTests = toml::from_str("[[cases]]\nexpect = \"deny\"").unwrap();
+        assert!(run_cases(&policy, &ambiguous).is_err());
+    }
+
+    #[test]
+    fn test_catalog_formats() {
+        let names = vec!["linux.system.cpu".to_string()];
+        let tool = serde_json::json!({"name": "linux.system.cpu", "inputSchema": {"type": "object"}});
+        assert_eq!(catalog_names(&serde_json::json!([tool])).unwrap(), names);
+        assert_eq!(catalog_names(&serde_json::json!({"tools": [tool]})).unwrap(), names);
+        assert_eq!(catalog_names(&serde_json::json!(["linux.system.cpu"])).unwrap(), names);
+        assert!(catalog_names(&serde_json::json!({"name": "linux.system.cpu"})).is_err());
+    }
+}
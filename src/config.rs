@@ -16,6 +16,96 @@ pub struct ServerConfig {
     pub bind: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// How upstream callers are identified for policy rules and the audit log
+    #[serde(default)]
+    pub identity: IdentityConfig,
+    /// How tool calls under a `require_approval` policy rule are decided
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    /// Where the operator API listens and the credential it requires
+    #[serde(default)]
+    pub admin: AdminConfig,
+}
+
+/// Sources of upstream caller identity.
+///
+/// The MCP client name from `initialize` is always used; the rest is opt-in.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IdentityConfig {
+    /// Static bearer tokens, each known to policy rules by its `id`
+    #[serde(default)]
+    pub tokens: Vec<BearerToken>,
+    /// File holding the shared secret that signs HS256 JWT bearer tokens
+    #[serde(default)]
+    pub jwt_secret_file: Option<String>,
+    /// Reject JWTs whose `iss` claim is not this
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+    /// Reject JWTs whose `aud` claim does not name this
+    #[serde(default)]
+    pub jwt_audience: Option<String>,
+    /// Header carrying the verified client certificate subject, set by the
+    /// TLS-terminating proxy in front of neurond (e.g. "X-SSL-Client-S-DN")
+    #[serde(default)]
+    pub cert_subject_header: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BearerToken {
+    pub id: String,
+    /// File holding the token
+    pub token_file: String,
+}
+
+/// Human approval of tool calls held by `require_approval` policy rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApprovalConfig {
+    /// Seconds a call waits for a decision before it is rejected
+    #[serde(default = "default_approval_ttl")]
+    pub ttl_secs: u64,
+    /// URL that is POSTed each pending call and may answer with a decision
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Ask the upstream client to confirm via elicitation when it supports it.
+    /// Its answer decides the call, so only enable this for interactive clients
+    /// where a human answers the prompt.
+    #[serde(default)]
+    pub elicit: bool,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_approval_ttl(),
+            webhook_url: None,
+            elicit: false,
+        }
+    }
+}
+
+/// The operator API, served on its own listener apart from the MCP endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminConfig {
+    /// Address of the admin listener
+    #[serde(default = "default_admin_bind")]
+    pub bind: String,
+    /// Listen on this Unix socket (mode 0600) instead of `bind`
+    #[serde(default)]
+    pub socket: Option<String>,
+    /// File holding the bearer token operators must send; without it the
+    /// admin API is not served
+    #[serde(default)]
+    pub token_file: Option<String>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            bind: default_admin_bind(),
+            socket: None,
+            token_file: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,28 +120,60 @@ pub struct RegistrationConfig {
     pub heartbeat_interval_secs: u64,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize)]
 pub struct FederationConfig {
     #[serde(default)]
     pub servers: Vec<DownstreamServer>,
+    /// Directory holding the cached tool catalogs of `start = "lazy"` downstreams
+    #[serde(default = "default_catalog_dir")]
+    pub catalog_dir: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            catalog_dir: default_catalog_dir(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct DownstreamServer {
     /// Namespace prefix for this downstream's tools (e.g., "linux", "redis")
     pub namespace: String,
     /// Transport configuration
     #[serde(flatten)]
     pub transport: DownstreamTransport,
-    /// Optional: restrict which tools are exposed upstream
+    /// Optional: glob allowlist of downstream tool names (un-prefixed) exposed upstream.
+    /// Tools that match no pattern are neither listed nor callable. Empty = expose all.
     #[serde(default)]
     pub expose: Vec<String>,
     /// Health check interval (default: 30s)
     #[serde(default = "default_healthcheck")]
     pub healthcheck_interval_secs: u64,
+    /// When to spawn the downstream: at boot (default) or on first use
+    #[serde(default)]
+    pub start: StartMode,
+    /// Optional: stop the downstream after this many seconds without requests.
+    /// It becomes dormant and is started again on the next call.
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// When a downstream is started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartMode {
+    /// Connect at boot
+    #[default]
+    Eager,
+    /// Advertise tools from the cached catalog and connect on first use.
+    /// Without a catalog yet, the downstream is started at boot to build one.
+    Lazy,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(tag = "transport")]
 pub enum DownstreamTransport {
     /// Connect to a local MCP server via HTTP SSE
@@ -67,7 +189,116 @@ pub enum DownstreamTransport {
         args: Vec<String>,
         #[serde(default)]
         env: HashMap<String, String>,
+        /// Optional: restrict the child's privileges and environment
+        #[serde(default)]
+        sandbox: Option<StdioSandbox>,
     },
+    /// Connect to a local MCP server via streamable HTTP over a Unix domain socket
+    #[serde(rename = "unix")]
+    Unix {
+        /// Socket path (e.g. "/run/mcpd/mcp.sock")
+        socket: String,
+        /// HTTP path of the MCP endpoint on that socket
+        #[serde(default = "default_unix_path")]
+        path: String,
+        /// Only use connections whose listening process runs as this uid (SO_PEERCRED)
+        #[serde(default)]
+        expected_uid: Option<u32>,
+    },
+    /// Connect to an MCP server on another host via streamable HTTP(S)
+    #[serde(rename = "remote")]
+    Remote {
+        url: String,
+        #[serde(flatten)]
+        auth: RemoteAuth,
+    },
+}
+
+/// Privilege and resource restrictions for a `stdio` downstream's child process.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct StdioSandbox {
+    /// Run as this user (name or numeric uid); also sets the group unless `group` is given
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Run as this group (name or numeric gid)
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Working directory of the child
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Start from an empty environment instead of inheriting neurond's
+    #[serde(default)]
+    pub env_clear: bool,
+    /// With `env_clear`: variables still inherited from neurond (e.g. "PATH", "LANG")
+    #[serde(default)]
+    pub env_allowlist: Vec<String>,
+    /// File mode creation mask, e.g. 0o077
+    #[serde(default)]
+    pub umask: Option<u32>,
+    /// Address space limit in bytes (RLIMIT_AS)
+    #[serde(default)]
+    pub memory_bytes: Option<u64>,
+    /// Maximum open file descriptors (RLIMIT_NOFILE)
+    #[serde(default)]
+    pub nofile: Option<u64>,
+    /// Maximum processes for the run-as user (RLIMIT_NPROC)
+    #[serde(default)]
+    pub nproc: Option<u64>,
+    /// Run in a new network namespace with no interfaces up
+    #[serde(default)]
+    pub no_network: bool,
+    /// Mount a fresh tmpfs over /tmp in a private mount namespace
+    #[serde(default)]
+    pub private_tmp: bool,
+    /// Let the child gain privileges through setuid binaries or file
+    /// capabilities; by default it runs with PR_SET_NO_NEW_PRIVS
+    #[serde(default)]
+    pub allow_new_privileges: bool,
+}
+
+/// Credentials and TLS settings for a `remote` downstream.
+///
+/// Secrets are read from files at every (re)connect, so rotated tokens and
+/// certificates are picked up without restarting neurond.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RemoteAuth {
+    /// Static headers sent with every request (e.g. `X-Tenant = "ops"`)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// File holding a bearer token for the `Authorization` header
+    #[serde(default)]
+    pub bearer_token_file: Option<String>,
+    /// PEM bundle of CA certificates trusted in addition to the built-in roots
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    /// PEM client certificate for mutual TLS (requires `client_key`)
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM private key for `client_cert`
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// Obtain bearer tokens via OAuth 2.1 client credentials (excludes `bearer_token_file`)
+    #[serde(default)]
+    pub oauth: Option<OAuthClientCredentials>,
+}
+
+/// OAuth client registration used for the client-credentials grant.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OAuthClientCredentials {
+    pub client_id: String,
+    /// File holding the client secret
+    pub client_secret_file: String,
+    /// Space-separated scopes to request
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Token endpoint to use instead of discovering one
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    /// Authorization server to use instead of the one the downstream names,
+    /// trusted to name a token endpoint on another origin; ignored if
+    /// `token_endpoint` is set
+    #[serde(default)]
+    pub issuer: Option<String>,
 }
 
 impl Config {
@@ -88,6 +319,14 @@ fn default_port() -> u16 {
     8443
 }
 
+fn default_approval_ttl() -> u64 {
+    300
+}
+
+fn default_admin_bind() -> String {
+    "127.0.0.1:8444".to_string()
+}
+
 fn default_heartbeat_interval() -> u64 {
     30
 }
@@ -96,6 +335,14 @@ fn default_healthcheck() -> u64 {
     30
 }
 
+fn default_catalog_dir() -> String {
+    "/var/lib/neurond/catalog".to_string()
+}
+
+fn default_unix_path() -> String {
+    "/mcp".to_string()
+}
+
 fn generate_node_id() -> String {
     let dev_path = "./node_id";
     let prod_path = "/var/lib/neurond/node_id";
@@ -128,17 +375,22 @@ pub const DEV_CONFIG_PATH: &str = "neurond.toml";
 
 /// Load config from the first available path
 pub fn load_config() -> anyhow::Result<Config> {
-    let path = if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() {
-        DEFAULT_CONFIG_PATH
+    let path = config_path()?;
+    tracing::info!("Loading config from {}", path);
+    Config::load_from_file(path)
+}
+
+/// The config file in use: the production path if present, else the dev path.
+pub fn config_path() -> anyhow::Result<&'static str> {
+    if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() {
+        Ok(DEFAULT_CONFIG_PATH)
     } else if std::path::Path::new(DEV_CONFIG_PATH).exists() {
-        DEV_CONFIG_PATH
+        Ok(DEV_CONFIG_PATH)
     } else {
         anyhow::bail!(
             "No config file found. Expected {} or {}",
             DEFAULT_CONFIG_PATH,
             DEV_CONFIG_PATH
         );
-    };
-    tracing::info!("Loading config from {}", path);
-    Config::load_from_file(path)
+    }
 }
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rmcp::model::Tool;
+
+use crate::federation::namespace;
+
+/// Path of the cached tool catalog for `namespace`.
+fn catalog_path(dir: &Path, namespace: &str) -> PathBuf {
+    dir.join(format!("{}.json", namespace))
+}
+
+/// Load the namespaced tools last discovered from a downstream.
+///
+/// Tools no longer matched by `expose` are dropped, so narrowing the allowlist takes
+/// effect before the downstream is started. Returns None if there is no usable catalog.
+pub fn load(dir: &Path, namespace: &str, expose: &[String]) -> Option<Vec<Tool>> {
+    let path = catalog_path(dir, namespace);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    let mut tools: Vec<Tool> = match serde_json::from_str(&contents) {
+        Ok(tools) => tools,
+        Err(e) => {
+            tracing::warn!(namespace = %namespace, path = %path.display(), error = %e, "Ignoring corrupt tool catalog");
+            return None;
+        }
+    };
+    tools.retain(|t| {
+        namespace::strip_namespace(namespace, &t.name)
+            .is_some_and(|name| namespace::is_exposed(expose, &name))
+    });
+    Some(tools)
+}
+
+/// Persist the namespaced tools of a downstream for the next lazy start.
+pub fn save(dir: &Path, namespace: &str, tools: &[Tool]) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create catalog directory: {}", dir.display()))?;
+    let path = catalog_path(dir, namespace);
+    // Write then rename so a crash never leaves a truncated catalog behind
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(tools)?)
+        .with_context(|| format!("Failed to write tool catalog: {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path)
+        .with_context(|| format!("Failed to write tool catalog: {}", path.display()))?;
+    Ok(())
+}
+
+/// Lazy downstreams with cached catalogs, which register dormant without
+/// spawning anything.
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::*;
+    use crate::config::FederationConfig;
+
+    pub fn tool(name: &str) -> Tool {
+        Tool::new(
+            name.to_string(),
+            "test tool",
+            serde_json::json!({"type": "object"}).as_object().unwrap().clone(),
+        )
+    }
+
+    /// Cache the named tools as the catalog of each namespace.
+    pub fn save_catalogs(dir: &Path, catalogs: &[(&str, &[&str])]) {
+        for (namespace, names) in catalogs {
+            let tools: Vec<Tool> = names.iter().map(|name| tool(name)).collect();
+            save(dir, namespace, &tools).unwrap();
+        }
+    }
+
+    /// Lazy `/bin/false` downstreams reading their catalogs from `dir`, each
+    /// given as `(namespace, expose)`.
+    pub fn lazy_config(dir: &Path, servers: &[(&str, &[&str])]) -> FederationConfig {
+        let servers: String = servers
+            .iter()
+            .map(|(namespace, expose)| {
+                format!(
+                    "[[servers]]\nnamespace = \"{namespace}\"\ntransport = \"stdio\"\ncommand = \"/bin/false\"\nstart = \"lazy\"\nexpose = {expose:?}\n"
+                )
+            })
+            .collect();
+        toml::from_str(&format!("catalog_dir = \"{}\"\n{servers}", dir.display())).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::tool;
+    use super::*;
+
+    #[test]
+    fn test_catalog_round_trip_applies_expose() {
+        let dir = std::env::temp_dir().join(format!("neurond-catalog-{}", uuid::Uuid::new_v4()));
+        assert!(load(&dir, "linux", &[]).is_none());
+
+        save(&dir, "linux", &[tool("linux.system.cpu"), tool("linux.service.restart")]).unwrap();
+        let all: Vec<String> = load(&dir, "linux", &[])
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert_eq!(all, vec!["linux.system.cpu", "linux.service.restart"]);
+
+        let exposed = load(&dir, "linux", &["system.*".to_string()]).unwrap();
+        assert_eq!(exposed.len(), 1);
+        assert_eq!(exposed[0].name, "linux.system.cpu");
+
+        std::fs::write(catalog_path(&dir, "linux"), "not json").unwrap();
+        assert!(load(&dir, "linux", &[]).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
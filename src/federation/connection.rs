@@ -1,7 +1,39 @@
+use crate::federation::handler::DownstreamClient;
+use crate::federation::lifecycle::LazyStart;
 use rmcp::model::Tool;
-use rmcp::service::RunningService;
-use rmcp::RoleClient;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Number of stderr lines kept per stdio downstream.
+pub const STDERR_TAIL_LINES: usize = 50;
+
+/// Ring buffer of the most recent stderr lines from a stdio downstream.
+///
+/// Cloning shares the buffer, so the reader task and the connection see the same lines.
+/// It survives restarts, which keeps the output of a crashed child around.
+#[derive(Clone, Default)]
+pub struct StderrTail(Arc<Mutex<VecDeque<String>>>);
+
+impl StderrTail {
+    pub fn push(&self, line: String) {
+        if let Ok(mut lines) = self.0.lock() {
+            if lines.len() == STDERR_TAIL_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+
+    /// Snapshot of the buffered lines, oldest first.
+    pub fn lines(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
 
 /// Lifecycle state of a downstream MCP server connection.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -12,6 +44,9 @@ pub enum ConnectionState {
     Starting,
     /// Connected and tools discovered
     Healthy,
+    /// Not running (lazy start or idle shutdown) — tools advertised from the catalog,
+    /// started on the next request
+    Dormant,
     /// Connection lost, attempting reconnect
     Restarting { attempt: u32 },
     /// Max retries exceeded — tools removed from registry
@@ -26,12 +61,20 @@ pub struct DownstreamConnection {
     pub state: ConnectionState,
     /// Cached list of tools from this downstream (namespaced)
     pub tools: Vec<Tool>,
+    /// Glob allowlist of downstream tool names exposed upstream (empty = all)
+    pub expose: Vec<String>,
     /// Active rmcp client service handle
-    pub client: Option<RunningService<RoleClient, ()>>,
+    pub client: Option<DownstreamClient>,
     /// Spawned child process handle (for stdio transport)
     pub child: Option<tokio::process::Child>,
     /// Last successful health check timestamp
     pub last_seen: Instant,
+    /// Recent stderr output of a stdio child (empty for other transports)
+    pub stderr: StderrTail,
+    /// How to start the downstream on demand while Dormant
+    pub lazy: Option<LazyStart>,
+    /// Cancelled when the downstream is removed, stopping its monitor
+    pub shutdown: CancellationToken,
 }
 
 impl DownstreamConnection {
@@ -40,9 +83,13 @@ impl DownstreamConnection {
             namespace,
             state: ConnectionState::Configured,
             tools: Vec::new(),
+            expose: Vec::new(),
             client: None,
             child: None,
             last_seen: Instant::now(),
+            stderr: StderrTail::default(),
+            lazy: None,
+            shutdown: CancellationToken::new(),
         }
     }
 
@@ -51,6 +98,11 @@ impl DownstreamConnection {
         self.state == ConnectionState::Healthy && self.client.is_some()
     }
 
+    /// Returns true if this downstream's tools should be listed upstream.
+    pub fn advertises_tools(&self) -> bool {
+        self.is_healthy() || self.state == ConnectionState::Dormant
+    }
+
     /// Transition to the Starting state.
     pub fn mark_starting(&mut self) {
         self.state = ConnectionState::Starting;
@@ -73,6 +125,12 @@ impl DownstreamConnection {
         self.tools.clear();
     }
 
+    /// Transition to the Dormant state — keeps the tool list, drops the client.
+    pub fn mark_dormant(&mut self) {
+        self.state = ConnectionState::Dormant;
+        self.client = None;
+    }
+
     /// Transition to the Failed state — gives up reconnecting.
     pub fn mark_failed(&mut self) {
         self.state = ConnectionState::Failed;
@@ -80,3 +138,36 @@ impl DownstreamConnection {
         self.client = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dormant_keeps_advertising_tools() {
+        let mut conn = DownstreamConnection::new("heavy".to_string());
+        assert!(!conn.advertises_tools());
+        conn.tools = vec![Tool::new(
+            "heavy.run",
+            "run",
+            serde_json::json!({"type": "object"}).as_object().unwrap().clone(),
+        )];
+        conn.mark_dormant();
+        assert!(conn.advertises_tools());
+        assert!(!conn.is_healthy());
+        assert_eq!(conn.tools.len(), 1);
+    }
+
+    #[test]
+    fn test_stderr_tail_keeps_last_lines() {
+        let tail = StderrTail::default();
+        let shared = tail.clone();
+        for i in 0..STDERR_TAIL_LINES + 2 {
+            shared.push(format!("line {i}"));
+        }
+        let lines = tail.lines();
+        assert_eq!(lines.len(), STDERR_TAIL_LINES);
+        assert_eq!(lines[0], "line 2");
+        assert_eq!(lines.last().unwrap(), &format!("line {}", STDERR_TAIL_LINES + 1));
+    }
+}
@@ -0,0 +1,17 @@
+use rmcp::model::LoggingMessageNotificationParam;
+
+/// Change notifications emitted by the federation layer.
+///
+/// Broadcast to every upstream session so it can relay the matching
+/// `notifications/*` message to its MCP client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FederationEvent {
+    /// The aggregated tool list changed (downstream notification, reconnect or failure).
+    ToolListChanged,
+    /// A downstream announced that its resource list changed.
+    ResourceListChanged,
+    /// A downstream announced that its prompt list changed.
+    PromptListChanged,
+    /// A downstream sent `notifications/message`; `logger` is already namespaced.
+    LogMessage(LoggingMessageNotificationParam),
+}
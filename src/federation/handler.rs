@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use crate::federation::connection::{DownstreamConnection, StderrTail};
+use crate::federation::events::FederationEvent;
+use crate::federation::manager::discover_tools;
+use crate::federation::namespace;
+use crate::security::audit::AuditLogger;
+use crate::security::identity::CallerIdentity;
+use crate::security::policy::PolicyStore;
+use rmcp::model::{
+    ClientCapabilities, ClientInfo, CreateElicitationRequestParams, CreateElicitationResult,
+    CreateMessageRequestParams, CreateMessageResult, ErrorCode, Implementation,
+    LoggingLevel, LoggingMessageNotificationParam, Meta, ProgressNotificationParam, ProgressToken,
+};
+use rmcp::service::{NotificationContext, Peer, RequestContext, RunningService, ServiceError};
+use rmcp::{ClientHandler, ErrorData as McpError, RoleClient, RoleServer};
+
+/// Running rmcp client for a downstream, driven by a [`DownstreamHandler`].
+pub type DownstreamClient = RunningService<RoleClient, DownstreamHandler>;
+
+/// How often an in-flight call checks whether its upstream session has gone away.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `_meta` key carrying neurond's ID for a downstream tool call. A downstream
+/// that echoes it in a sampling or elicitation request ties the request to
+/// that call.
+pub const CALL_ID_META_KEY: &str = "neurond/callId";
+
+/// The upstream request that caused a downstream call.
+#[derive(Clone)]
+pub struct UpstreamContext {
+    /// Upstream session that issued the request
+    pub peer: Peer<RoleServer>,
+    /// Distinguishes that session from other upstream sessions
+    pub session_id: String,
+    /// Progress token supplied by the upstream client, if it asked for progress
+    pub progress_token: Option<ProgressToken>,
+    /// Cancelled when the upstream client sends `notifications/cancelled`
+    pub ct: CancellationToken,
+    /// Policy that gates server-to-client requests made on behalf of this call
+    pub policy: PolicyStore,
+    /// Identity of the upstream caller, for policy decisions on its behalf
+    pub caller: CallerIdentity,
+    /// Audit log for those server-to-client requests
+    pub audit: Arc<AuditLogger>,
+}
+
+impl UpstreamContext {
+    /// Resolves when the upstream request is cancelled or its session closes.
+    pub async fn cancelled(&self) {
+        let session_closed = async {
+            while !self.peer.is_transport_closed() {
+                tokio::time::sleep(SESSION_POLL_INTERVAL).await;
+            }
+        };
+        tokio::select! {
+            _ = self.ct.cancelled() => {}
+            _ = session_closed => {}
+        }
+    }
+}
+
+/// In-flight downstream calls, keyed by the progress token neurond sent downstream.
+type InflightCalls = Arc<Mutex<HashMap<ProgressToken, InflightCall>>>;
+
+struct InflightCall {
+    /// Sent downstream under [`CALL_ID_META_KEY`]
+    call_id: String,
+    upstream: UpstreamContext,
+}
+
+/// Removes an in-flight call from its handler's registry when dropped.
+pub struct InflightGuard {
+    calls: InflightCalls,
+    token: ProgressToken,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.remove(&self.token);
+        }
+    }
+}
+
+/// Requests currently routed to a downstream and when the last one ended.
+struct Activity {
+    active: usize,
+    last: Instant,
+}
+
+/// Marks a request to the downstream as finished when dropped.
+pub struct ActivityGuard(Arc<Mutex<Activity>>);
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        if let Ok(mut activity) = self.0.lock() {
+            activity.active -= 1;
+            activity.last = Instant::now();
+        }
+    }
+}
+
+/// Client-side handler for one downstream MCP server.
+///
+/// Reacts to server-initiated notifications by refreshing the cached state
+/// for its namespace and broadcasting a [`FederationEvent`] upstream.
+#[derive(Clone)]
+pub struct DownstreamHandler {
+    namespace: String,
+    expose: Vec<String>,
+    downstreams: Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: broadcast::Sender<FederationEvent>,
+    inflight: InflightCalls,
+    stderr: StderrTail,
+    activity: Arc<Mutex<Activity>>,
+}
+
+impl DownstreamHandler {
+    pub fn new(
+        namespace: String,
+        expose: Vec<String>,
+        downstreams: Arc<RwLock<Vec<DownstreamConnection>>>,
+        events: broadcast::Sender<FederationEvent>,
+        stderr: StderrTail,
+    ) -> Self {
+        Self {
+            namespace,
+            expose,
+            downstreams,
+            events,
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+            stderr,
+            activity: Arc::new(Mutex::new(Activity {
+                active: 0,
+                last: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Where a stdio child's stderr lines are kept for this namespace.
+    pub fn stderr(&self) -> &StderrTail {
+        &self.stderr
+    }
+
+    /// Glob allowlist of downstream tool names exposed upstream.
+    pub fn expose(&self) -> &[String] {
+        &self.expose
+    }
+
+    /// Register an in-flight call so downstream notifications can reach its upstream caller.
+    ///
+    /// `downstream_token` is the progress token attached to the downstream request
+    /// and `call_id` the ID sent with it under [`CALL_ID_META_KEY`].
+    /// The call is unregistered when the returned guard is dropped.
+    pub fn track_call(&self, downstream_token: ProgressToken, call_id: String, upstream: UpstreamContext) -> InflightGuard {
+        if let Ok(mut calls) = self.inflight.lock() {
+            calls.insert(downstream_token.clone(), InflightCall { call_id, upstream });
+        }
+        InflightGuard {
+            calls: self.inflight.clone(),
+            token: downstream_token,
+        }
+    }
+
+    /// Count a request routed to this downstream until the returned guard is dropped.
+    pub fn begin_request(&self) -> ActivityGuard {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.active += 1;
+            activity.last = Instant::now();
+        }
+        ActivityGuard(self.activity.clone())
+    }
+
+    /// Time since the last request finished, or None while requests are in flight.
+    pub fn idle_for(&self) -> Option<Duration> {
+        let activity = self.activity.lock().ok()?;
+        (activity.active == 0).then(|| activity.last.elapsed())
+    }
+
+    /// Look up the upstream caller for a downstream progress token.
+    fn upstream_for(&self, downstream_token: &ProgressToken) -> Option<UpstreamContext> {
+        let calls = self.inflight.lock().ok()?;
+        calls.get(downstream_token).map(|call| call.upstream.clone())
+    }
+
+    /// Pick the upstream session for a server-to-client request, or explain why there is none.
+    ///
+    /// A request whose `_meta` carries a call ID goes to that call's session.
+    /// Otherwise it can only go to the session of the in-flight calls if they
+    /// all belong to one session; it is refused rather than guessed at.
+    fn upstream_for_request(&self, method: &str, meta: &Meta) -> Result<UpstreamContext, McpError> {
+        let refuse = |reason: String| McpError {
+            code: ErrorCode::INVALID_REQUEST,
+            message: format!("{} {}", method, reason).into(),
+            data: None,
+        };
+        let calls = self
+            .inflight
+            .lock()
+            .map_err(|_| refuse("cannot be routed: in-flight call registry is poisoned".to_string()))?;
+
+        if let Some(call_id) = meta.0.get(CALL_ID_META_KEY) {
+            return calls
+                .values()
+                .find(|call| call_id.as_str() == Some(call.call_id.as_str()))
+                .map(|call| call.upstream.clone())
+                .ok_or_else(|| refuse(format!("refers to tool call {} which is not in flight", call_id)));
+        }
+
+        let mut upstreams = calls.values().map(|call| &call.upstream);
+        let Some(upstream) = upstreams.next() else {
+            return Err(refuse("is only available while a tool call is in flight".to_string()));
+        };
+        if upstreams.any(|other| other.session_id != upstream.session_id) {
+            return Err(refuse(format!(
+                "is ambiguous: tool calls from several upstream sessions are in flight; \
+                 echo the tool call's _meta {:?} to pick one",
+                CALL_ID_META_KEY
+            )));
+        }
+        Ok(upstream.clone())
+    }
+
+    /// Forward a server-to-client request upstream, abandoning it if the downstream cancels.
+    async fn forward<T>(
+        &self,
+        method: &str,
+        upstream: &UpstreamContext,
+        request: impl std::future::Future<Output = Result<T, ServiceError>>,
+        context: &RequestContext<RoleClient>,
+        params: &serde_json::Value,
+    ) -> Result<T, McpError> {
+        let start = std::time::Instant::now();
+        let result = tokio::select! {
+            result = request => result.map_err(|e| match e {
+                ServiceError::McpError(e) => e,
+                other => McpError {
+                    code: ErrorCode::INTERNAL_ERROR,
+                    message: format!("Upstream {} failed: {}", method, other).into(),
+                    data: None,
+                },
+            }),
+            _ = context.ct.cancelled() => Err(McpError {
+                code: ErrorCode::INTERNAL_ERROR,
+                message: format!("{} cancelled by downstream", method).into(),
+                data: None,
+            }),
+        };
+
+        let duration = start.elapsed().as_millis() as u64;
+        let result_str = if result.is_ok() { "success" } else { "error" };
+        if let Err(e) = upstream
+            .audit
+            .log_request(method, &self.namespace, params, "allowed", result_str, duration)
+            .await
+        {
+            tracing::error!(namespace = %self.namespace, error = %e, "Audit logging failed");
+        }
+        result
+    }
+}
+
+/// Error returned when the upstream client did not declare a capability.
+fn unsupported_upstream(method: &str) -> McpError {
+    McpError {
+        code: ErrorCode::METHOD_NOT_FOUND,
+        message: format!("Upstream client does not support {}", method).into(),
+        data: None,
+    }
+}
+
+#[allow(clippy::manual_async_fn)]
+impl ClientHandler for DownstreamHandler {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder()
+                .enable_elicitation()
+                .enable_sampling()
+                .build(),
+            client_info: Implementation {
+                name: "neurond".to_string(),
+                title: Some("neurond Federation Proxy".to_string()),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                description: None,
+                icons: None,
+                website_url: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParams,
+        context: RequestContext<RoleClient>,
+    ) -> impl std::future::Future<Output = Result<CreateMessageResult, McpError>> + Send + '_ {
+        async move {
+            const METHOD: &str = "sampling/createMessage";
+            let upstream = self.upstream_for_request(METHOD, &context.meta)?;
+            // Log the request shape, not the conversation itself
+            let summary = serde_json::json!({
+                "messages": params.messages.len(),
+                "max_tokens": params.max_tokens,
+            });
+
+            let policy = upstream.policy.load();
+            let decision = policy.decide_sampling(&self.namespace, &upstream.caller);
+            if let Some(candidate) = policy.candidate() {
+                let shadow = candidate.decide_sampling(&self.namespace, &upstream.caller);
+                upstream.audit.log_shadow(METHOD, &self.namespace, &summary, &decision, &shadow).await;
+            }
+            if !decision.is_allowed() {
+                let _ = upstream
+                    .audit
+                    .with_policy(&decision)
+                    .log_request(METHOD, &self.namespace, &summary, "denied", "blocked", 0)
+                    .await;
+                return Err(McpError {
+                    code: ErrorCode::INVALID_REQUEST,
+                    message: format!("Access denied to sampling for namespace {} by security policy", self.namespace).into(),
+                    data: serde_json::to_value(&decision).ok(),
+                });
+            }
+            let supported = upstream
+                .peer
+                .peer_info()
+                .is_some_and(|info| info.capabilities.sampling.is_some());
+            if !supported {
+                return Err(unsupported_upstream(METHOD));
+            }
+
+            tracing::info!(namespace = %self.namespace, "Relaying sampling request upstream");
+            let request = upstream.peer.create_message(params);
+            self.forward(METHOD, &upstream, request, &context, &summary).await
+        }
+    }
+
+    fn create_elicitation(
+        &self,
+        request: CreateElicitationRequestParams,
+        context: RequestContext<RoleClient>,
+    ) -> impl std::future::Future<Output = Result<CreateElicitationResult, McpError>> + Send + '_ {
+        async move {
+            const METHOD: &str = "elicitation/create";
+            let upstream = self.upstream_for_request(METHOD, &context.meta)?;
+            let supported = upstream
+                .peer
+                .peer_info()
+                .is_some_and(|info| info.capabilities.elicitation.is_some());
+            if !supported {
+                return Err(unsupported_upstream(METHOD));
+            }
+
+            tracing::info!(namespace = %self.namespace, "Relaying elicitation request upstream");
+            let summary = match &request {
+                CreateElicitationRequestParams::FormElicitationParams { message, .. } => {
+                    serde_json::json!({ "mode": "form", "message": message })
+                }
+                CreateElicitationRequestParams::UrlElicitationParams { message, url, .. } => {
+                    serde_json::json!({ "mode": "url", "message": message, "url": url })
+                }
+            };
+            let request = upstream.peer.create_elicitation(request);
+            self.forward(METHOD, &upstream, request, &context, &summary).await
+        }
+    }
+
+    fn on_tool_list_changed(
+        &self,
+        context: NotificationContext<RoleClient>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        async move {
+            tracing::info!(namespace = %self.namespace, "Downstream tool list changed — re-listing");
+
+            let tools = match discover_tools(&self.namespace, &self.expose, &context.peer).await {
+                Ok(tools) => tools,
+                Err(e) => {
+                    tracing::warn!(namespace = %self.namespace, error = %e, "Failed to re-list downstream tools");
+                    return;
+                }
+            };
+
+            let count = tools.len();
+            {
+                let mut downstreams = self.downstreams.write().await;
+                // Only refresh a live connection; a stale client may still emit after a reconnect
+                let Some(conn) = downstreams
+                    .iter_mut()
+                    .find(|c| c.namespace == self.namespace && c.is_healthy())
+                else {
+                    return;
+                };
+                conn.tools = tools;
+            }
+
+            tracing::info!(namespace = %self.namespace, tools = count, "Downstream tools refreshed");
+            let _ = self.events.send(FederationEvent::ToolListChanged);
+        }
+    }
+
+    fn on_resource_list_changed(
+        &self,
+        _context: NotificationContext<RoleClient>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        // Resources are listed live, so there is no cache to refresh — just relay
+        tracing::debug!(namespace = %self.namespace, "Downstream resource list changed");
+        let _ = self.events.send(FederationEvent::ResourceListChanged);
+        std::future::ready(())
+    }
+
+    fn on_prompt_list_changed(
+        &self,
+        _context: NotificationContext<RoleClient>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        tracing::debug!(namespace = %self.namespace, "Downstream prompt list changed");
+        let _ = self.events.send(FederationEvent::PromptListChanged);
+        std::future::ready(())
+    }
+
+    fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        let namespace = self.namespace.as_str();
+        let logger = params.logger.as_deref().unwrap_or_default();
+        let data = &params.data;
+        match params.level {
+            LoggingLevel::Debug => tracing::debug!(namespace, logger, %data, "Downstream log"),
+            LoggingLevel::Info | LoggingLevel::Notice => {
+                tracing::info!(namespace, logger, %data, "Downstream log")
+            }
+            LoggingLevel::Warning => tracing::warn!(namespace, logger, %data, "Downstream log"),
+            LoggingLevel::Error
+            | LoggingLevel::Critical
+            | LoggingLevel::Alert
+            | LoggingLevel::Emergency => tracing::error!(namespace, logger, %data, "Downstream log"),
+        }
+
+        // Upstream sees the namespace as (the prefix of) the logger name
+        let logger = match &params.logger {
+            Some(logger) => namespace::prefix_tool_name(namespace, logger),
+            None => namespace.to_string(),
+        };
+        let _ = self.events.send(FederationEvent::LogMessage(LoggingMessageNotificationParam {
+            logger: Some(logger),
+            ..params
+        }));
+        std::future::ready(())
+    }
+
+    fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        async move {
+            // Relay only to callers that asked for progress, rewriting to their token
+            let Some(upstream) = self.upstream_for(&params.progress_token) else {
+                return;
+            };
+            let Some(progress_token) = upstream.progress_token else {
+                return;
+            };
+            let relayed = ProgressNotificationParam {
+                progress_token,
+                ..params
+            };
+            if let Err(e) = upstream.peer.notify_progress(relayed).await {
+                tracing::debug!(namespace = %self.namespace, error = %e, "Failed to relay progress upstream");
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use http::header::{ACCEPT, CONTENT_TYPE, WWW_AUTHENTICATE};
+use http::{HeaderName, HeaderValue, StatusCode};
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use rmcp::transport::streamable_http_client::{
+    AuthRequiredError, StreamableHttpClient, StreamableHttpError, StreamableHttpPostResponse,
+};
+use sse_stream::{Error as SseError, Sse, SseStream};
+use tokio_util::sync::DropGuard;
+
+use crate::federation::oauth::OAuthClient;
+
+const EVENT_STREAM_MIME_TYPE: &str = "text/event-stream";
+const JSON_MIME_TYPE: &str = "application/json";
+const HEADER_SESSION_ID: &str = "Mcp-Session-Id";
+const HEADER_LAST_EVENT_ID: &str = "Last-Event-Id";
+const HEADER_MCP_PROTOCOL_VERSION: &str = "MCP-Protocol-Version";
+
+type HttpError = StreamableHttpError<reqwest::Error>;
+
+/// Streamable HTTP client for downstreams, backed by neurond's own `reqwest` build.
+///
+/// rmcp only implements its client for the `reqwest` it bundles, which has no TLS and
+/// cannot be pointed at a Unix socket. Wrapping ours lets each downstream get a
+/// `reqwest::Client` configured for its transport.
+#[derive(Clone)]
+pub struct HttpClient {
+    http: reqwest::Client,
+    oauth: Option<Arc<OAuthClient>>,
+    /// Dropped with the last clone of the client
+    _guard: Option<Arc<DropGuard>>,
+}
+
+impl HttpClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            oauth: None,
+            _guard: None,
+        }
+    }
+
+    /// Keep `guard` until the client is dropped, e.g. to stop a relay `http` connects through.
+    pub fn holding(mut self, guard: DropGuard) -> Self {
+        self._guard = Some(Arc::new(guard));
+        self
+    }
+
+    /// Authenticate with OAuth access tokens instead of a static bearer token.
+    pub fn with_oauth(mut self, oauth: OAuthClient) -> Self {
+        self.oauth = Some(Arc::new(oauth));
+        self
+    }
+
+    /// Send a request built by `build`, attaching the bearer token.
+    ///
+    /// With OAuth, a 401 triggers one retry with a freshly issued token.
+    async fn send(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+        auth_token: Option<String>,
+    ) -> Result<reqwest::Response, HttpError> {
+        let Some(oauth) = &self.oauth else {
+            return with_bearer(build(), auth_token).send().await.map_err(client_error);
+        };
+
+        let token = oauth.current_token().await.map_err(oauth_error)?;
+        let response = with_bearer(build(), token).send().await.map_err(client_error)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let challenge = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let token = oauth.authorize(challenge.as_deref()).await.map_err(oauth_error)?;
+        build().bearer_auth(token).send().await.map_err(client_error)
+    }
+}
+
+impl StreamableHttpClient for HttpClient {
+    type Error = reqwest::Error;
+
+    async fn post_message(
+        &self,
+        uri: Arc<str>,
+        message: ClientJsonRpcMessage,
+        session_id: Option<Arc<str>>,
+        auth_token: Option<String>,
+        custom_headers: HashMap<HeaderName, HeaderValue>,
+    ) -> Result<StreamableHttpPostResponse, HttpError> {
+        let reserved = [
+            ACCEPT.as_str(),
+            HEADER_SESSION_ID,
+            HEADER_MCP_PROTOCOL_VERSION,
+            HEADER_LAST_EVENT_ID,
+        ];
+        if let Some(name) = custom_headers
+            .keys()
+            .find(|name| reserved.iter().any(|r| name.as_str().eq_ignore_ascii_case(r)))
+        {
+            return Err(StreamableHttpError::ReservedHeaderConflict(name.to_string()));
+        }
+
+        let build = || {
+            let mut request = self.http.post(uri.as_ref()).header(ACCEPT, accept());
+            for (name, value) in &custom_headers {
+                request = request.header(name, value);
+            }
+            if let Some(session_id) = &session_id {
+                request = request.header(HEADER_SESSION_ID, session_id.as_ref());
+            }
+            request.json(&message)
+        };
+        let response = self.send(build, auth_token).await?;
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            if let Some(header) = response.headers().get(WWW_AUTHENTICATE) {
+                return Err(StreamableHttpError::AuthRequired(AuthRequiredError {
+                    www_authenticate_header: String::from_utf8_lossy(header.as_bytes()).into_owned(),
+                }));
+            }
+        }
+        if matches!(status, StatusCode::ACCEPTED | StatusCode::NO_CONTENT) {
+            return Ok(StreamableHttpPostResponse::Accepted);
+        }
+        let response = response.error_for_status().map_err(client_error)?;
+
+        let session_id = response
+            .headers()
+            .get(HEADER_SESSION_ID)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        match content_type(&response) {
+            Some(ct) if ct.starts_with(EVENT_STREAM_MIME_TYPE) => {
+                let events = SseStream::from_byte_stream(response.bytes_stream()).boxed();
+                Ok(StreamableHttpPostResponse::Sse(events, session_id))
+            }
+            Some(ct) if ct.starts_with(JSON_MIME_TYPE) => {
+                let message: ServerJsonRpcMessage = response.json().await.map_err(client_error)?;
+                Ok(StreamableHttpPostResponse::Json(message, session_id))
+            }
+            other => Err(StreamableHttpError::UnexpectedContentType(other)),
+        }
+    }
+
+    async fn delete_session(
+        &self,
+        uri: Arc<str>,
+        session_id: Arc<str>,
+        auth_token: Option<String>,
+    ) -> Result<(), HttpError> {
+        let build = || {
+            self.http
+                .delete(uri.as_ref())
+                .header(HEADER_SESSION_ID, session_id.as_ref())
+        };
+        let response = self.send(build, auth_token).await?;
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            tracing::debug!("Downstream does not support deleting sessions");
+            return Ok(());
+        }
+        response.error_for_status().map_err(client_error)?;
+        Ok(())
+    }
+
+    async fn get_stream(
+        &self,
+        uri: Arc<str>,
+        session_id: Arc<str>,
+        last_event_id: Option<String>,
+        auth_token: Option<String>,
+    ) -> Result<BoxStream<'static, Result<Sse, SseError>>, HttpError> {
+        let build = || {
+            let request = self
+                .http
+                .get(uri.as_ref())
+                .header(ACCEPT, accept())
+                .header(HEADER_SESSION_ID, session_id.as_ref());
+            match &last_event_id {
+                Some(last_event_id) => request.header(HEADER_LAST_EVENT_ID, last_event_id),
+                None => request,
+            }
+        };
+        let response = self.send(build, auth_token).await?;
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Err(StreamableHttpError::ServerDoesNotSupportSse);
+        }
+        let response = response.error_for_status().map_err(client_error)?;
+        match content_type(&response) {
+            Some(ct) if ct.starts_with(EVENT_STREAM_MIME_TYPE) || ct.starts_with(JSON_MIME_TYPE) => {
+                Ok(SseStream::from_byte_stream(response.bytes_stream()).boxed())
+            }
+            other => Err(StreamableHttpError::UnexpectedContentType(other)),
+        }
+    }
+}
+
+fn accept() -> String {
+    [EVENT_STREAM_MIME_TYPE, JSON_MIME_TYPE].join(", ")
+}
+
+fn content_type(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|ct| String::from_utf8_lossy(ct.as_bytes()).into_owned())
+}
+
+fn with_bearer(request: reqwest::RequestBuilder, token: Option<String>) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+fn client_error(e: reqwest::Error) -> HttpError {
+    StreamableHttpError::Client(e)
+}
+
+fn oauth_error(e: anyhow::Error) -> HttpError {
+    StreamableHttpError::UnexpectedServerResponse(format!("OAuth authorization failed: {:#}", e).into())
+}
@@ -1,8 +1,405 @@
-// Downstream spawn, restart policy, health checking, graceful shutdown.
-//
-// spawn_stdio_downstream()    — spawn a child process MCP server via stdio
-// connect_localhost_downstream() — connect to a localhost HTTP MCP server
-// monitor_downstream()        — watches for disconnect, restarts with backoff
-// shutdown_all_downstreams()  — graceful shutdown on SIGTERM/SIGINT
-//
-// TODO: implement — see tasks/todo.md "FEAT: Lifecycle management"
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use anyhow::Context;
+
+use crate::config::DownstreamServer;
+use crate::federation::catalog;
+use crate::federation::connection::{ConnectionState, DownstreamConnection};
+use crate::federation::events::FederationEvent;
+use crate::federation::handler::DownstreamHandler;
+use crate::federation::manager::discover_tools;
+use crate::federation::transport;
+use rmcp::model::{ClientRequest, PingRequest};
+use rmcp::service::Peer;
+use rmcp::RoleClient;
+
+/// Maximum reconnection attempts before marking a downstream as Failed.
+pub const MAX_RETRIES: u32 = 5;
+
+/// Upper bound for the exponential reconnect backoff.
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// How long a health-check ping may take before the downstream is considered dead.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before reconnect attempt `attempt` (1-based): 1s, 2s, 4s, … capped at 60s.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let exp = attempt.saturating_sub(1).min(16);
+    Duration::from_secs((1u64 << exp).min(MAX_BACKOFF_SECS))
+}
+
+/// Send an MCP `ping` to a downstream. Returns false on error or timeout.
+pub async fn ping(peer: &Peer<RoleClient>) -> bool {
+    let request = ClientRequest::PingRequest(PingRequest {
+        method: Default::default(),
+        extensions: Default::default(),
+    });
+    matches!(
+        tokio::time::timeout(PING_TIMEOUT, peer.send_request(request)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// What is needed to start a Dormant downstream on demand.
+///
+/// Set for downstreams with `start = "lazy"` or an `idle_timeout_secs`.
+#[derive(Clone)]
+pub struct LazyStart {
+    config: DownstreamServer,
+    handler: DownstreamHandler,
+    catalog_dir: PathBuf,
+    /// Held while starting, so concurrent first calls spawn the child once
+    starting: Arc<Mutex<()>>,
+}
+
+impl LazyStart {
+    pub fn new(config: DownstreamServer, handler: DownstreamHandler, catalog_dir: PathBuf) -> Self {
+        Self {
+            config,
+            handler,
+            catalog_dir,
+            starting: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.config.namespace
+    }
+
+    /// Persist the downstream's tools for the next lazy start.
+    pub fn save_catalog(&self, tools: &[rmcp::model::Tool]) {
+        if let Err(e) = catalog::save(&self.catalog_dir, self.namespace(), tools) {
+            tracing::warn!(namespace = %self.namespace(), error = %e, "Failed to save tool catalog");
+        }
+    }
+}
+
+/// Start a Dormant downstream, refresh its catalog and mark it Healthy.
+///
+/// Callers that race with a start in progress wait for it and return without
+/// starting again. On failure the downstream stays Dormant, so the next request retries.
+pub async fn wake(
+    downstreams: &Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: &broadcast::Sender<FederationEvent>,
+    lazy: &LazyStart,
+) -> anyhow::Result<()> {
+    let _starting = lazy.starting.lock().await;
+    let namespace = lazy.namespace();
+
+    let dormant = with_conn(downstreams, namespace, |c| {
+        let dormant = c.state == ConnectionState::Dormant;
+        if dormant {
+            c.mark_starting();
+        }
+        dormant
+    })
+    .await;
+    if dormant != Some(true) {
+        return Ok(());
+    }
+
+    tracing::info!(namespace = %namespace, "Starting dormant downstream");
+    let started = async {
+        let client = transport::connect_downstream(&lazy.config.transport, lazy.handler.clone()).await?;
+        let tools = discover_tools(namespace, &lazy.config.expose, client.peer())
+            .await
+            .context("Failed to list tools from downstream")?;
+        anyhow::Ok((client, tools))
+    }
+    .await;
+
+    let (client, tools) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            with_conn(downstreams, namespace, |c| c.mark_dormant()).await;
+            tracing::error!(
+                namespace = %namespace,
+                error = %e,
+                stderr = %lazy.handler.stderr().lines().join("\n"),
+                "Failed to start dormant downstream"
+            );
+            return Err(e);
+        }
+    };
+
+    lazy.save_catalog(&tools);
+    let count = tools.len();
+    let changed = with_conn(downstreams, namespace, |c| {
+        let changed = c.tools != tools;
+        c.mark_healthy(tools);
+        c.client = Some(client);
+        changed
+    })
+    .await;
+    // The catalog was stale — tell upstream clients about the real tool set
+    if changed == Some(true) {
+        let _ = events.send(FederationEvent::ToolListChanged);
+    }
+    tracing::info!(namespace = %namespace, tools = count, "Downstream started on demand");
+    Ok(())
+}
+
+/// Stop a Healthy downstream that has served no request for `timeout`, leaving it Dormant.
+///
+/// Returns true if it was stopped.
+async fn stop_if_idle(
+    downstreams: &RwLock<Vec<DownstreamConnection>>,
+    lazy: &LazyStart,
+    timeout: Duration,
+) -> bool {
+    // Decided under the write lock: requests resolve their route under the read
+    // lock, so none can pick up this client between the check and the stop
+    let stopped = with_conn(downstreams, lazy.namespace(), |c| {
+        let idle = lazy.handler.idle_for().is_some_and(|idle| idle >= timeout);
+        if !c.is_healthy() || !idle {
+            return None;
+        }
+        let client = c.client.take();
+        c.mark_dormant();
+        client.map(|client| (client, c.tools.clone()))
+    })
+    .await
+    .flatten();
+
+    let Some((client, tools)) = stopped else {
+        return false;
+    };
+    tracing::info!(
+        namespace = %lazy.namespace(),
+        idle_secs = timeout.as_secs(),
+        "Stopping idle downstream"
+    );
+    lazy.save_catalog(&tools);
+    let _ = client.cancel().await;
+    true
+}
+
+/// Spawn a background task that health-checks one downstream and reconnects it on failure.
+///
+/// The task exits once the downstream is marked Failed or removed from the registry,
+/// or when `shutdown` is cancelled — which a removal does, so a monitor never outlives
+/// its connection into a replacement registered under the same namespace.
+/// Every change to the downstream's tool set is announced on `events`.
+pub fn spawn_monitor(
+    downstreams: Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: broadcast::Sender<FederationEvent>,
+    config: DownstreamServer,
+    handler: DownstreamHandler,
+    shutdown: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = monitor_downstream(downstreams, events, config, handler) => {}
+            _ = shutdown.cancelled() => {}
+        }
+    })
+}
+
+/// Ping the downstream every `healthcheck_interval_secs`; on failure, respawn (stdio)
+/// or reconnect (HTTP) with exponential backoff and re-discover its tools.
+///
+/// Dormant downstreams are left alone, and with `idle_timeout_secs` an idle one is
+/// stopped instead of pinged.
+async fn monitor_downstream(
+    downstreams: Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: broadcast::Sender<FederationEvent>,
+    config: DownstreamServer,
+    handler: DownstreamHandler,
+) {
+    let namespace = config.namespace.clone();
+    let interval = Duration::from_secs(config.healthcheck_interval_secs.max(1));
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        // Clone the peer so the lock is not held across the ping
+        let (peer, lazy) = {
+            let guard = downstreams.read().await;
+            let Some(conn) = guard.iter().find(|c| c.namespace == namespace) else {
+                tracing::debug!(namespace = %namespace, "Downstream removed — stopping monitor");
+                return;
+            };
+            // Not running on purpose, or being started on demand
+            if matches!(conn.state, ConnectionState::Dormant | ConnectionState::Starting) {
+                continue;
+            }
+            let peer = conn
+                .client
+                .as_ref()
+                .filter(|_| conn.is_healthy())
+                .map(|c| c.peer().clone());
+            (peer, conn.lazy.clone())
+        };
+
+        if let (Some(lazy), Some(timeout)) = (&lazy, config.idle_timeout_secs) {
+            if stop_if_idle(&downstreams, lazy, Duration::from_secs(timeout)).await {
+                continue;
+            }
+        }
+
+        if let Some(peer) = peer {
+            if ping(&peer).await {
+                with_conn(&downstreams, &namespace, |c| c.last_seen = Instant::now()).await;
+                continue;
+            }
+            tracing::warn!(namespace = %namespace, "Downstream health check failed");
+        }
+
+        if !reconnect(&downstreams, &events, &config, &handler).await {
+            return;
+        }
+    }
+}
+
+/// Tear down the current client and retry connecting until success or `MAX_RETRIES`.
+///
+/// Returns true once the downstream is Healthy again, false if it was marked Failed.
+async fn reconnect(
+    downstreams: &Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: &broadcast::Sender<FederationEvent>,
+    config: &DownstreamServer,
+    handler: &DownstreamHandler,
+) -> bool {
+    let namespace = &config.namespace;
+
+    // Dropping the old service kills a stdio child; close it explicitly first
+    if let Some(Some(old)) = with_conn(downstreams, namespace, |c| c.client.take()).await {
+        let _ = old.cancel().await;
+    }
+
+    for _ in 0..MAX_RETRIES {
+        let Some((attempt, had_tools)) = with_conn(downstreams, namespace, |c| {
+            let had_tools = !c.tools.is_empty();
+            c.mark_restarting();
+            match c.state {
+                ConnectionState::Restarting { attempt } => (attempt, had_tools),
+                _ => (0, had_tools),
+            }
+        })
+        .await
+        else {
+            return false;
+        };
+        if had_tools {
+            let _ = events.send(FederationEvent::ToolListChanged);
+        }
+
+        let delay = backoff_delay(attempt);
+        tracing::info!(
+            namespace = %namespace,
+            attempt,
+            delay_secs = delay.as_secs(),
+            "Reconnecting to downstream"
+        );
+        tokio::time::sleep(delay).await;
+
+        let client = match transport::connect_downstream(&config.transport, handler.clone()).await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!(namespace = %namespace, attempt, error = %e, "Reconnect failed");
+                continue;
+            }
+        };
+
+        match discover_tools(namespace, &config.expose, client.peer()).await {
+            Ok(tools) => {
+                let count = tools.len();
+                with_conn(downstreams, namespace, |c| {
+                    c.mark_healthy(tools);
+                    c.client = Some(client);
+                })
+                .await;
+                let _ = events.send(FederationEvent::ToolListChanged);
+                tracing::info!(namespace = %namespace, tools = count, "Downstream recovered");
+                return true;
+            }
+            Err(e) => {
+                tracing::warn!(namespace = %namespace, attempt, error = %e, "Tool discovery failed after reconnect");
+            }
+        }
+    }
+
+    with_conn(downstreams, namespace, |c| c.mark_failed()).await;
+    tracing::error!(
+        namespace = %namespace,
+        retries = MAX_RETRIES,
+        stderr = %handler.stderr().lines().join("\n"),
+        "Downstream failed — max retries exceeded, tools removed"
+    );
+    false
+}
+
+/// Apply `f` to the connection for `namespace` under the write lock.
+/// Returns None if the namespace is no longer registered.
+async fn with_conn<T>(
+    downstreams: &RwLock<Vec<DownstreamConnection>>,
+    namespace: &str,
+    f: impl FnOnce(&mut DownstreamConnection) -> T,
+) -> Option<T> {
+    let mut guard = downstreams.write().await;
+    guard.iter_mut().find(|c| c.namespace == namespace).map(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(2), Duration::from_secs(2));
+        assert_eq!(backoff_delay(5), Duration::from_secs(16));
+        assert_eq!(backoff_delay(7), Duration::from_secs(MAX_BACKOFF_SECS));
+        assert_eq!(backoff_delay(u32::MAX), Duration::from_secs(MAX_BACKOFF_SECS));
+    }
+
+    struct IdleDownstream;
+    impl rmcp::ServerHandler for IdleDownstream {}
+
+    #[tokio::test]
+    async fn test_ping_closed_downstream_fails() {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let server = tokio::spawn(async move {
+            rmcp::service::serve_server(IdleDownstream, server_io).await
+        });
+        let client = rmcp::service::serve_client((), client_io).await.unwrap();
+        let server = server.await.unwrap().unwrap();
+
+        assert!(ping(client.peer()).await);
+
+        server.cancel().await.unwrap();
+        assert!(!ping(client.peer()).await);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_exits_when_downstream_removed() {
+        let downstreams = Arc::new(RwLock::new(Vec::new()));
+        let config: DownstreamServer = toml::from_str(
+            r#"
+            namespace = "gone"
+            transport = "localhost"
+            url = "http://127.0.0.1:1/mcp"
+            healthcheck_interval_secs = 1
+            "#,
+        )
+        .unwrap();
+
+        let (events, _) = broadcast::channel(1);
+        let handler = DownstreamHandler::new(
+            config.namespace.clone(),
+            Vec::new(),
+            downstreams.clone(),
+            events.clone(),
+            Default::default(),
+        );
+        let handle = spawn_monitor(downstreams, events, config, handler, CancellationToken::new());
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("monitor should stop for an unregistered namespace")
+            .unwrap();
+    }
+}
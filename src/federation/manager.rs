@@ -1,14 +1,34 @@
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use serde::Serialize;
+use tokio::sync::{broadcast, Mutex, RwLock};
 
-use crate::config::{DownstreamServer, FederationConfig};
-use crate::federation::connection::{ConnectionState, DownstreamConnection};
+use crate::config::{DownstreamServer, FederationConfig, StartMode};
+use crate::federation::catalog;
+use crate::federation::connection::{ConnectionState, DownstreamConnection, StderrTail};
+use crate::federation::events::FederationEvent;
+use crate::federation::handler::{ActivityGuard, DownstreamHandler, UpstreamContext, CALL_ID_META_KEY};
+use crate::federation::lifecycle::{self, LazyStart};
 use crate::federation::namespace;
 use crate::federation::transport;
-use rmcp::model::{CallToolRequestParams, CallToolResult, Tool};
+use rmcp::model::{
+    CallToolRequest, CallToolRequestParams, CallToolResult, CancelledNotificationParam,
+    ClientRequest, CompleteRequestParams, CompleteResult, GetPromptRequestParams, GetPromptResult, Meta, Prompt, ReadResourceRequestParams,
+    ReadResourceResult, Resource, ResourceTemplate, ServerCapabilities, ServerResult, Tool,
+};
+use rmcp::service::{Peer, PeerRequestOptions};
+use rmcp::RoleClient;
 
-/// Maximum reconnection attempts before marking a downstream as Failed.
-const MAX_RETRIES: u32 = 5;
+/// Capacity of the federation event channel; slow subscribers see `Lagged`.
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// How long a removed downstream may keep serving in-flight requests.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a draining downstream is checked for remaining requests.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Manages all downstream MCP server connections.
 ///
@@ -19,44 +39,117 @@ const MAX_RETRIES: u32 = 5;
 /// 4. Routing tool calls to the correct downstream
 pub struct FederationManager {
     downstreams: Arc<RwLock<Vec<DownstreamConnection>>>,
+    events: broadcast::Sender<FederationEvent>,
+    /// Configuration each registered namespace was started with, diffed on reload
+    configs: Mutex<HashMap<String, DownstreamServer>>,
 }
 
 impl Default for FederationManager {
     fn default() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
         Self {
             downstreams: Arc::new(RwLock::new(Vec::new())),
+            events,
+            configs: Mutex::new(HashMap::new()),
         }
     }
 }
 
+/// Namespaces touched by a [`FederationManager::reload`].
+#[derive(Debug, Default, PartialEq)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ReloadSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 impl FederationManager {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Subscribe to federation change events (tool list changes, …).
+    pub fn subscribe(&self) -> broadcast::Receiver<FederationEvent> {
+        self.events.subscribe()
+    }
+
+    /// Broadcast a change event to every upstream session.
+    pub fn notify(&self, event: FederationEvent) {
+        // No receivers simply means no upstream session is connected
+        let _ = self.events.send(event);
+    }
+
+    /// Build the client handler for a downstream namespace.
+    fn handler_for(&self, namespace: &str, expose: &[String], stderr: StderrTail) -> DownstreamHandler {
+        DownstreamHandler::new(
+            namespace.to_string(),
+            expose.to_vec(),
+            self.downstreams.clone(),
+            self.events.clone(),
+            stderr,
+        )
+    }
+
     /// Initialize all downstream connections from config.
     pub async fn init_from_config(&self, config: &FederationConfig) -> anyhow::Result<()> {
+        let catalog_dir = Path::new(&config.catalog_dir);
         for server_config in &config.servers {
-            self.add_downstream(server_config).await;
+            self.add_downstream(server_config, catalog_dir).await;
         }
         Ok(())
     }
 
     /// Add and connect a single downstream MCP server.
-    async fn add_downstream(&self, config: &DownstreamServer) {
+    ///
+    /// A `start = "lazy"` downstream with a cached catalog is registered Dormant instead.
+    async fn add_downstream(&self, config: &DownstreamServer, catalog_dir: &Path) {
         let namespace = config.namespace.clone();
-        tracing::info!(namespace = %namespace, "Connecting to downstream MCP server");
-
         let mut conn = DownstreamConnection::new(namespace.clone());
-        conn.mark_starting();
+        conn.expose = config.expose.clone();
+
+        let handler = self.handler_for(&namespace, &config.expose, conn.stderr.clone());
+        if config.start == StartMode::Lazy || config.idle_timeout_secs.is_some() {
+            conn.lazy = Some(LazyStart::new(
+                config.clone(),
+                handler.clone(),
+                catalog_dir.to_path_buf(),
+            ));
+        }
 
-        match transport::connect_downstream(&config.transport).await {
+        let cached = match config.start {
+            StartMode::Lazy => catalog::load(catalog_dir, &namespace, &config.expose),
+            StartMode::Eager => None,
+        };
+        if let Some(tools) = cached {
+            tracing::info!(
+                namespace = %namespace,
+                tools = tools.len(),
+                "Downstream dormant until first use — {} tools from catalog",
+                tools.len()
+            );
+            conn.tools = tools;
+            conn.mark_dormant();
+            self.register(conn, config, handler).await;
+            return;
+        }
+
+        tracing::info!(namespace = %namespace, "Connecting to downstream MCP server");
+        conn.mark_starting();
+        match transport::connect_downstream(&config.transport, handler.clone()).await {
             Ok(client) => {
                 // Discover tools from the downstream via the peer handle
-                match client.peer().list_all_tools().await {
-                    Ok(raw_tools) => {
-                        let namespaced = namespace::namespace_tools(&namespace, &raw_tools);
+                match discover_tools(&namespace, &conn.expose, client.peer()).await {
+                    Ok(namespaced) => {
                         let count = namespaced.len();
+                        if let Some(lazy) = &conn.lazy {
+                            lazy.save_catalog(&namespaced);
+                        }
                         conn.mark_healthy(namespaced);
                         conn.client = Some(client);
                         tracing::info!(
@@ -78,15 +171,110 @@ impl FederationManager {
             }
         }
 
+        self.register(conn, config, handler).await;
+    }
+
+    /// Add a connection to the registry and start monitoring it.
+    async fn register(&self, conn: DownstreamConnection, config: &DownstreamServer, handler: DownstreamHandler) {
+        let shutdown = conn.shutdown.clone();
         self.downstreams.write().await.push(conn);
+        self.configs
+            .lock()
+            .await
+            .insert(config.namespace.clone(), config.clone());
+        lifecycle::spawn_monitor(
+            self.downstreams.clone(),
+            self.events.clone(),
+            config.clone(),
+            handler,
+            shutdown,
+        );
+    }
+
+    /// Apply a re-read `[federation]` section to the running downstreams.
+    ///
+    /// New namespaces are connected, removed ones drained, and ones whose
+    /// configuration changed are drained and connected again. Upstream sessions
+    /// are told to re-list if anything changed.
+    pub async fn reload(&self, config: &FederationConfig) -> ReloadSummary {
+        let mut summary = ReloadSummary::default();
+        let live = self.namespaces().await;
+        let known = self.configs.lock().await.clone();
+
+        for ns in &live {
+            match config.servers.iter().find(|s| &s.namespace == ns) {
+                None => summary.removed.push(ns.clone()),
+                Some(new) if known.get(ns) != Some(new) => summary.changed.push(ns.clone()),
+                Some(_) => {}
+            }
+        }
+        summary.added = config
+            .servers
+            .iter()
+            .filter(|s| !live.contains(&s.namespace))
+            .map(|s| s.namespace.clone())
+            .collect();
+
+        for ns in summary.removed.iter().chain(&summary.changed) {
+            self.remove_downstream(ns).await;
+        }
+        let catalog_dir = Path::new(&config.catalog_dir);
+        for server_config in &config.servers {
+            let ns = &server_config.namespace;
+            if summary.added.contains(ns) || summary.changed.contains(ns) {
+                self.add_downstream(server_config, catalog_dir).await;
+            }
+        }
+
+        if !summary.is_empty() {
+            self.notify(FederationEvent::ToolListChanged);
+            self.notify(FederationEvent::ResourceListChanged);
+            self.notify(FederationEvent::PromptListChanged);
+        }
+        summary
+    }
+
+    /// Unregister a downstream and stop it once its in-flight requests finish.
+    ///
+    /// New requests stop resolving to it immediately. Requests already running get
+    /// up to `DRAIN_TIMEOUT` before the client is closed.
+    async fn remove_downstream(&self, namespace: &str) {
+        self.configs.lock().await.remove(namespace);
+        let conn = {
+            let mut downstreams = self.downstreams.write().await;
+            let Some(pos) = downstreams.iter().position(|c| c.namespace == namespace) else {
+                return;
+            };
+            downstreams.remove(pos)
+        };
+        conn.shutdown.cancel();
+
+        let Some(client) = conn.client else {
+            tracing::info!(namespace = %namespace, "Downstream removed");
+            return;
+        };
+        let namespace = namespace.to_string();
+        tokio::spawn(async move {
+            let handler = client.service().clone();
+            let drained = async {
+                while handler.idle_for().is_none() {
+                    tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+                }
+            };
+            if tokio::time::timeout(DRAIN_TIMEOUT, drained).await.is_err() {
+                tracing::warn!(namespace = %namespace, "Drain timed out — closing downstream with requests in flight");
+            }
+            let _ = client.cancel().await;
+            tracing::info!(namespace = %namespace, "Downstream drained and removed");
+        });
     }
 
-    /// Get the aggregated tool list from all healthy downstreams.
+    /// Get the aggregated tool list from all healthy and dormant downstreams.
     pub async fn list_all_tools(&self) -> Vec<Tool> {
         let downstreams = self.downstreams.read().await;
         downstreams
             .iter()
-            .filter(|c| c.is_healthy())
+            .filter(|c| c.advertises_tools())
             .flat_map(|c| c.tools.clone())
             .collect()
     }
@@ -100,81 +288,437 @@ impl FederationManager {
     /// Route a tool call to the correct downstream by namespace.
     ///
     /// Strips the namespace prefix, forwards the call, and returns the result.
+    /// When `upstream` is given, downstream progress is relayed to the caller's session
+    /// and the downstream request is cancelled if the upstream request is cancelled
+    /// or its session drops.
     pub async fn route_tool_call(
         &self,
         tool_name: &str,
         arguments: serde_json::Value,
+        upstream: Option<UpstreamContext>,
     ) -> Result<CallToolResult, rmcp::ErrorData> {
-        let downstreams = self.downstreams.read().await;
-        let namespaces: Vec<String> = downstreams.iter().map(|c| c.namespace.clone()).collect();
+        // Tools hidden by `expose` are indistinguishable from unknown tools, and
+        // never wake a dormant downstream
+        if !self.is_exposed(tool_name).await {
+            return Err(rmcp::ErrorData {
+                code: rmcp::model::ErrorCode::METHOD_NOT_FOUND,
+                message: format!("No downstream registered for tool: {tool_name}").into(),
+                data: None,
+            });
+        }
+        let route = self
+            .resolve(tool_name, rmcp::model::ErrorCode::METHOD_NOT_FOUND)
+            .await?;
+        let target_ns = &route.namespace;
+
+        // Build the downstream call params
+        let params = CallToolRequestParams {
+            name: route.original.into(),
+            arguments: arguments.as_object().cloned(),
+            meta: None,
+            task: None,
+        };
+        let request = ClientRequest::CallToolRequest(CallToolRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+
+        // Tag the call so server-to-client requests it causes can name it
+        let call_id = uuid::Uuid::new_v4().to_string();
+        let meta = upstream.as_ref().map(|_| {
+            let mut meta = Meta::new();
+            meta.0.insert(CALL_ID_META_KEY.to_string(), call_id.clone().into());
+            meta
+        });
+        let options = PeerRequestOptions { timeout: None, meta };
 
-        let (target_ns, original_name) =
-            namespace::resolve_namespace(&namespaces, tool_name).ok_or_else(|| {
-                rmcp::ErrorData {
-                    code: rmcp::model::ErrorCode::METHOD_NOT_FOUND,
-                    message: format!("No downstream registered for tool: {tool_name}").into(),
+        let downstream_error = |e| downstream_error(target_ns, e);
+
+        // rmcp assigns every downstream request its own progress token; key the
+        // in-flight registry on it so tokens from different upstream sessions never collide
+        let handle = route
+            .peer
+            .send_request_with_option(request, options)
+            .await
+            .map_err(downstream_error)?;
+        let request_id = handle.id.clone();
+        let _inflight = upstream
+            .clone()
+            .map(|u| route.handler.track_call(handle.progress_token.clone(), call_id, u));
+
+        let cancelled = async {
+            match &upstream {
+                Some(u) => u.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        let response = tokio::select! {
+            response = handle.await_response() => response.map_err(downstream_error)?,
+            _ = cancelled => {
+                tracing::info!(namespace = %target_ns, tool = %tool_name, "Upstream cancelled — cancelling downstream call");
+                let _ = route
+                    .peer
+                    .notify_cancelled(CancelledNotificationParam {
+                        request_id,
+                        reason: Some("cancelled by upstream".to_string()),
+                    })
+                    .await;
+                return Err(rmcp::ErrorData {
+                    code: rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    message: format!("Tool call {tool_name} cancelled by upstream").into(),
                     data: None,
+                });
+            }
+        };
+
+        match response {
+            ServerResult::CallToolResult(result) => Ok(result),
+            _ => Err(downstream_error(rmcp::ServiceError::UnexpectedResponse)),
+        }
+    }
+
+    /// Get the aggregated resource list from all healthy downstreams that serve resources.
+    ///
+    /// Resources are not cached — each call queries the downstreams live.
+    pub async fn list_all_resources(&self) -> Vec<Resource> {
+        let mut all = Vec::new();
+        for (ns, peer) in self.healthy_peers(|caps| caps.resources.is_some()).await {
+            match peer.list_all_resources().await {
+                Ok(resources) => all.extend(namespace::namespace_resources(&ns, &resources)),
+                Err(e) => {
+                    tracing::warn!(namespace = %ns, error = %e, "Failed to list downstream resources");
+                }
+            }
+        }
+        all
+    }
+
+    /// Get the aggregated resource template list from all healthy downstreams.
+    pub async fn list_all_resource_templates(&self) -> Vec<ResourceTemplate> {
+        let mut all = Vec::new();
+        for (ns, peer) in self.healthy_peers(|caps| caps.resources.is_some()).await {
+            match peer.list_all_resource_templates().await {
+                Ok(templates) => {
+                    all.extend(namespace::namespace_resource_templates(&ns, &templates))
+                }
+                Err(e) => {
+                    tracing::warn!(namespace = %ns, error = %e, "Failed to list downstream resource templates");
+                }
+            }
+        }
+        all
+    }
+
+    /// Route a `resources/read` to the owning downstream by URI namespace.
+    ///
+    /// Strips the namespace from the URI, forwards the read, and re-prefixes returned URIs.
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, rmcp::ErrorData> {
+        let route = self
+            .resolve(uri, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND)
+            .await?;
+        let target_ns = route.namespace;
+
+        let result = route
+            .peer
+            .read_resource(ReadResourceRequestParams {
+                meta: None,
+                uri: route.original,
+            })
+            .await
+            .map_err(|e| downstream_error(&target_ns, e))?;
+
+        Ok(ReadResourceResult {
+            contents: namespace::namespace_resource_contents(&target_ns, result.contents),
+        })
+    }
+
+    /// Get the aggregated prompt list from all healthy downstreams that serve prompts.
+    pub async fn list_all_prompts(&self) -> Vec<Prompt> {
+        let mut all = Vec::new();
+        for (ns, peer) in self.healthy_peers(|caps| caps.prompts.is_some()).await {
+            match peer.list_all_prompts().await {
+                Ok(prompts) => all.extend(namespace::namespace_prompts(&ns, &prompts)),
+                Err(e) => {
+                    tracing::warn!(namespace = %ns, error = %e, "Failed to list downstream prompts");
                 }
+            }
+        }
+        all
+    }
+
+    /// Route a `prompts/get` to the owning downstream by prompt namespace.
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<GetPromptResult, rmcp::ErrorData> {
+        let route = self
+            .resolve(name, rmcp::model::ErrorCode::INVALID_PARAMS)
+            .await?;
+        let target_ns = route.namespace;
+
+        route.peer.get_prompt(GetPromptRequestParams {
+            meta: None,
+            name: route.original,
+            arguments,
+        })
+        .await
+        .map_err(|e| downstream_error(&target_ns, e))
+    }
+
+    /// Route a `completion/complete` to the downstream owning the referenced
+    /// prompt or resource template.
+    ///
+    /// The reference is un-prefixed before forwarding; completion values are argument
+    /// values, not names, so they are returned unchanged. A downstream that does not
+    /// advertise completions yields an empty result.
+    pub async fn complete(
+        &self,
+        request: CompleteRequestParams,
+    ) -> Result<CompleteResult, rmcp::ErrorData> {
+        let route = self
+            .resolve(
+                namespace::reference_target(&request.r#ref),
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+            )
+            .await?;
+        let target_ns = route.namespace;
+
+        let supported = route
+            .peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.completions.is_some());
+        if !supported {
+            return Ok(CompleteResult::default());
+        }
+
+        route
+            .peer
+            .complete(CompleteRequestParams {
+                r#ref: namespace::retarget_reference(request.r#ref, route.original),
+                ..request
+            })
+            .await
+            .map_err(|e| downstream_error(&target_ns, e))
+    }
+
+    /// Resolve a namespaced name or URI to its downstream and clone its handles.
+    ///
+    /// A Dormant downstream is started first. The lock is released before returning,
+    /// so callers may await downstream I/O. `not_found` is the error code used when
+    /// no namespace matches.
+    async fn resolve(
+        &self,
+        name: &str,
+        not_found: rmcp::model::ErrorCode,
+    ) -> Result<Route, rmcp::ErrorData> {
+        let lazy = match self.lookup(name, not_found).await? {
+            Resolved::Route(route) => return Ok(route),
+            Resolved::Dormant(lazy) => lazy,
+        };
+        lifecycle::wake(&self.downstreams, &self.events, &lazy)
+            .await
+            .map_err(|e| rmcp::ErrorData {
+                code: rmcp::model::ErrorCode::INTERNAL_ERROR,
+                message: format!("Failed to start downstream '{}': {:#}", lazy.namespace(), e).into(),
+                data: None,
             })?;
+        match self.lookup(name, not_found).await? {
+            Resolved::Route(route) => Ok(route),
+            Resolved::Dormant(lazy) => Err(rmcp::ErrorData {
+                code: rmcp::model::ErrorCode::INTERNAL_ERROR,
+                message: format!("Downstream '{}' is not healthy", lazy.namespace()).into(),
+                data: None,
+            }),
+        }
+    }
 
-        let conn = downstreams
+    /// One registry lookup for [`resolve`](Self::resolve).
+    async fn lookup(
+        &self,
+        name: &str,
+        not_found: rmcp::model::ErrorCode,
+    ) -> Result<Resolved, rmcp::ErrorData> {
+        let downstreams = self.downstreams.read().await;
+        let namespaces: Vec<String> = downstreams.iter().map(|c| c.namespace.clone()).collect();
+
+        let (target_ns, original) =
+            namespace::resolve_namespace(&namespaces, name).ok_or_else(|| rmcp::ErrorData {
+                code: not_found,
+                message: format!("No downstream registered for: {name}").into(),
+                data: None,
+            })?;
+
+        if let Some(lazy) = downstreams
+            .iter()
+            .find(|c| c.namespace == target_ns && c.state == ConnectionState::Dormant)
+            .and_then(|c| c.lazy.clone())
+        {
+            return Ok(Resolved::Dormant(Box::new(lazy)));
+        }
+
+        let client = downstreams
             .iter()
             .find(|c| c.namespace == target_ns && c.is_healthy())
+            .and_then(|c| c.client.as_ref())
             .ok_or_else(|| rmcp::ErrorData {
                 code: rmcp::model::ErrorCode::INTERNAL_ERROR,
                 message: format!("Downstream '{target_ns}' is not healthy").into(),
                 data: None,
             })?;
 
-        let client = conn.client.as_ref().ok_or_else(|| rmcp::ErrorData {
-            code: rmcp::model::ErrorCode::INTERNAL_ERROR,
-            message: format!("No active client for downstream '{target_ns}'").into(),
-            data: None,
-        })?;
+        // Counted while the lock is held, so an idle stop cannot race this request
+        let handler = client.service().clone();
+        Ok(Resolved::Route(Route {
+            namespace: target_ns.to_string(),
+            original,
+            peer: client.peer().clone(),
+            _activity: handler.begin_request(),
+            handler,
+        }))
+    }
 
-        // Build the downstream call params
-        let params = CallToolRequestParams {
-            name: original_name.into(),
-            arguments: arguments.as_object().cloned(),
-            meta: None,
-            task: None,
+    /// Whether a namespaced tool name belongs to a downstream whose `expose`
+    /// allowlist admits it, judged from the registry without starting anything.
+    async fn is_exposed(&self, tool_name: &str) -> bool {
+        let downstreams = self.downstreams.read().await;
+        let namespaces: Vec<String> = downstreams.iter().map(|c| c.namespace.clone()).collect();
+        let Some((target_ns, original)) = namespace::resolve_namespace(&namespaces, tool_name) else {
+            return false;
         };
+        downstreams
+            .iter()
+            .find(|c| c.namespace == target_ns)
+            .is_some_and(|c| namespace::is_exposed(&c.expose, &original))
+    }
 
-        let result = client
-            .peer()
-            .call_tool(params)
-            .await
-            .map_err(|e| rmcp::ErrorData {
-                code: rmcp::model::ErrorCode::INTERNAL_ERROR,
-                message: format!("Downstream '{target_ns}' error: {e}").into(),
-                data: None,
-            })?;
-
-        Ok(result)
+    /// Clone the peer handles of healthy downstreams whose advertised capabilities match.
+    ///
+    /// Dormant downstreams are left out: only their tools are cached, so their
+    /// resources and prompts are not listed until something starts them.
+    async fn healthy_peers(
+        &self,
+        supports: impl Fn(&ServerCapabilities) -> bool,
+    ) -> Vec<(String, Peer<RoleClient>)> {
+        let downstreams = self.downstreams.read().await;
+        downstreams
+            .iter()
+            .filter(|c| c.is_healthy())
+            .filter_map(|c| {
+                let peer = c.client.as_ref()?.peer();
+                supports(&peer.peer_info()?.capabilities)
+                    .then(|| (c.namespace.clone(), peer.clone()))
+            })
+            .collect()
     }
 
-    /// Get status of all downstream connections (for diagnostics).
-    pub async fn status_summary(&self) -> Vec<(String, String)> {
+    /// State and stderr tail of every downstream, for the admin status endpoint.
+    pub async fn status(&self) -> Vec<DownstreamStatus> {
         let downstreams = self.downstreams.read().await;
         downstreams
             .iter()
-            .map(|c| {
-                let state = match &c.state {
+            .map(|c| DownstreamStatus {
+                namespace: c.namespace.clone(),
+                state: match &c.state {
                     ConnectionState::Configured => "configured",
                     ConnectionState::Starting => "starting",
                     ConnectionState::Healthy => "healthy",
+                    ConnectionState::Dormant => "dormant",
                     ConnectionState::Restarting { .. } => "restarting",
                     ConnectionState::Failed => "failed",
+                },
+                stderr: c.stderr.lines(),
+            })
+            .collect()
+    }
+
+    /// Get status of all downstream connections (for diagnostics).
+    ///
+    /// A failed stdio downstream reports the last lines of its stderr after "failed: ".
+    pub async fn status_summary(&self) -> Vec<(String, String)> {
+        self.status()
+            .await
+            .into_iter()
+            .map(|status| {
+                let summary = match status.state {
+                    "failed" if !status.stderr.is_empty() => format!("failed: {}", status.stderr.join("\n")),
+                    state => state.to_string(),
                 };
-                (c.namespace.clone(), state.to_string())
+                (status.namespace, summary)
             })
             .collect()
     }
 }
 
+/// One downstream as reported by `GET /api/v1/admin/status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DownstreamStatus {
+    pub namespace: String,
+    /// "configured", "starting", "healthy", "dormant", "restarting" or "failed"
+    pub state: &'static str,
+    /// Last lines a stdio child wrote to stderr, oldest first
+    pub stderr: Vec<String>,
+}
+
+/// A downstream resolved from a namespaced name, cloned out of the registry.
+struct Route {
+    namespace: String,
+    /// Name or URI with the namespace prefix stripped
+    original: String,
+    peer: Peer<RoleClient>,
+    handler: DownstreamHandler,
+    /// Keeps the downstream from being stopped as idle while the request runs
+    _activity: ActivityGuard,
+}
+
+/// Outcome of a registry lookup: a live route, or a downstream to start first.
+enum Resolved {
+    Route(Route),
+    Dormant(Box<LazyStart>),
+}
+
+/// Discover tools from a downstream peer and apply the `expose` allowlist.
+///
+/// Warns about allowlist patterns that match nothing and returns the namespaced tools.
+/// Performs network I/O — callers must not hold the downstreams lock.
+pub(crate) async fn discover_tools(
+    namespace: &str,
+    expose: &[String],
+    peer: &Peer<RoleClient>,
+) -> Result<Vec<Tool>, rmcp::ServiceError> {
+    let raw_tools = peer.list_all_tools().await?;
+    for pattern in namespace::unmatched_expose_patterns(expose, &raw_tools) {
+        tracing::warn!(
+            namespace = %namespace,
+            pattern = %pattern,
+            "expose pattern matches no downstream tool"
+        );
+    }
+    Ok(namespace::namespace_tools(namespace, &raw_tools, expose))
+}
+
+/// The error returned upstream for a failed downstream request. JSON-RPC errors
+/// from the downstream (e.g. RESOURCE_NOT_FOUND, INVALID_PARAMS) are passed on
+/// as is; transport failures become INTERNAL_ERROR.
+fn downstream_error(namespace: &str, error: rmcp::ServiceError) -> rmcp::ErrorData {
+    match error {
+        rmcp::ServiceError::McpError(error) => error,
+        error => rmcp::ErrorData {
+            code: rmcp::model::ErrorCode::INTERNAL_ERROR,
+            message: format!("Downstream '{namespace}' error: {error}").into(),
+            data: None,
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::federation::catalog::fixture;
+    use crate::security::audit::AuditLogger;
+    use crate::security::policy::{Policy, PolicyStore};
 
     #[tokio::test]
     async fn test_manager_new_has_no_downstreams() {
@@ -187,7 +731,7 @@ mod tests {
     async fn test_manager_route_unknown_namespace_returns_error() {
         let mgr = FederationManager::new();
         let result = mgr
-            .route_tool_call("unknown.tool", serde_json::json!({}))
+            .route_tool_call("unknown.tool", serde_json::json!({}), None)
             .await;
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -201,6 +745,768 @@ mod tests {
         assert!(summary.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_manager_status_summary_reports_stderr_of_failed_downstream() {
+        let mgr = FederationManager::new();
+        let mut conn = DownstreamConnection::new("tool".to_string());
+        conn.stderr.push("error: config not found".to_string());
+        conn.mark_failed();
+        mgr.downstreams.write().await.push(conn);
+
+        let summary = mgr.status_summary().await;
+        assert_eq!(
+            summary,
+            vec![("tool".to_string(), "failed: error: config not found".to_string())]
+        );
+        assert_eq!(
+            mgr.status().await,
+            vec![DownstreamStatus {
+                namespace: "tool".to_string(),
+                state: "failed",
+                stderr: vec!["error: config not found".to_string()],
+            }]
+        );
+    }
+
+    /// Minimal in-process downstream exposing a mutable set of tools.
+    ///
+    /// `call_tool` reports progress for "progress" and "slow"; "slow" then blocks
+    /// until cancelled and signals `cancelled`.
+    #[derive(Clone)]
+    struct MockDownstream {
+        tools: Arc<std::sync::Mutex<Vec<&'static str>>>,
+        cancelled: Arc<tokio::sync::Notify>,
+    }
+
+    impl rmcp::ServerHandler for MockDownstream {
+        fn get_info(&self) -> rmcp::model::ServerInfo {
+            rmcp::model::ServerInfo {
+                capabilities: rmcp::model::ServerCapabilities::builder()
+                    .enable_completions()
+                    .enable_prompts()
+                    .enable_tools()
+                    .enable_resources()
+                    .build(),
+                ..Default::default()
+            }
+        }
+
+        async fn complete(
+            &self,
+            request: CompleteRequestParams,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<CompleteResult, rmcp::ErrorData> {
+            // Echo what the downstream saw so tests can check the un-prefixed reference
+            let target = namespace::reference_target(&request.r#ref);
+            let value = format!("{}:{}", target, request.argument.value);
+            Ok(CompleteResult {
+                completion: rmcp::model::CompletionInfo::new(vec![value]).unwrap(),
+            })
+        }
+
+        async fn list_prompts(
+            &self,
+            _request: Option<rmcp::model::PaginatedRequestParams>,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<rmcp::model::ListPromptsResult, rmcp::ErrorData> {
+            Ok(rmcp::model::ListPromptsResult {
+                prompts: vec![Prompt::new("runbook", Some("Mock runbook"), None)],
+                next_cursor: None,
+                meta: None,
+            })
+        }
+
+        async fn get_prompt(
+            &self,
+            request: GetPromptRequestParams,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<GetPromptResult, rmcp::ErrorData> {
+            Ok(GetPromptResult {
+                description: Some(request.name),
+                messages: vec![],
+            })
+        }
+
+        async fn list_resources(
+            &self,
+            _request: Option<rmcp::model::PaginatedRequestParams>,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<rmcp::model::ListResourcesResult, rmcp::ErrorData> {
+            Ok(rmcp::model::ListResourcesResult {
+                resources: vec![Resource::new(
+                    rmcp::model::RawResource::new("file:///var/log/syslog", "syslog"),
+                    None,
+                )],
+                next_cursor: None,
+                meta: None,
+            })
+        }
+
+        async fn read_resource(
+            &self,
+            request: ReadResourceRequestParams,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<ReadResourceResult, rmcp::ErrorData> {
+            if request.uri == "file:///missing" {
+                return Err(rmcp::ErrorData::resource_not_found("no such file", None));
+            }
+            Ok(ReadResourceResult {
+                contents: vec![rmcp::model::ResourceContents::text("log line", request.uri)],
+            })
+        }
+
+        async fn list_tools(
+            &self,
+            _request: Option<rmcp::model::PaginatedRequestParams>,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<rmcp::model::ListToolsResult, rmcp::ErrorData> {
+            let tools = self
+                .tools
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|name| Tool::new(*name, "mock tool", serde_json::Map::new()))
+                .collect();
+            Ok(rmcp::model::ListToolsResult {
+                tools,
+                next_cursor: None,
+                meta: None,
+            })
+        }
+
+        async fn call_tool(
+            &self,
+            request: CallToolRequestParams,
+            context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<CallToolResult, rmcp::ErrorData> {
+            if request.name == "progress" || request.name == "slow" {
+                let progress_token = context.meta.get_progress_token().unwrap();
+                context
+                    .peer
+                    .notify_progress(rmcp::model::ProgressNotificationParam {
+                        progress_token,
+                        progress: 1.0,
+                        total: Some(2.0),
+                        message: Some("halfway".into()),
+                    })
+                    .await
+                    .unwrap();
+            }
+            if request.name == "sample" {
+                // Well-behaved downstreams echo the call's _meta tag
+                let echo = request.arguments.as_ref().is_some_and(|args| args.contains_key("echo_call_id"));
+                let call_id = context.meta.0.get(CALL_ID_META_KEY).cloned();
+                let meta = call_id.filter(|_| echo).map(|id| {
+                    let mut meta = Meta::new();
+                    meta.0.insert(CALL_ID_META_KEY.to_string(), id);
+                    meta
+                });
+                let sampled = context
+                    .peer
+                    .create_message(rmcp::model::CreateMessageRequestParams {
+                        meta,
+                        task: None,
+                        messages: vec![rmcp::model::SamplingMessage::user_text("summarize")],
+                        model_preferences: None,
+                        system_prompt: None,
+                        include_context: None,
+                        temperature: None,
+                        max_tokens: 16,
+                        stop_sequences: None,
+                        metadata: None,
+                        tools: None,
+                        tool_choice: None,
+                    })
+                    .await;
+                return Ok(match sampled {
+                    Ok(result) => CallToolResult::success(vec![rmcp::model::Content::text(
+                        result.message.content.into_vec()[0].as_text().unwrap().text.clone(),
+                    )]),
+                    Err(e) => CallToolResult::error(vec![rmcp::model::Content::text(e.to_string())]),
+                });
+            }
+            if request.name == "slow" {
+                context.ct.cancelled().await;
+                self.cancelled.notify_one();
+            }
+            Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                request.name.to_string(),
+            )]))
+        }
+    }
+
+    /// Connect a mock downstream over an in-memory duplex pipe and register it.
+    ///
+    /// Returns the mock (to mutate its tools) and the server-side peer (to emit notifications).
+    async fn add_mock_downstream(
+        mgr: &FederationManager,
+        namespace: &str,
+        tools: Vec<&'static str>,
+        expose: Vec<String>,
+    ) -> (MockDownstream, Peer<rmcp::RoleServer>) {
+        let mock = MockDownstream {
+            tools: Arc::new(std::sync::Mutex::new(tools)),
+            cancelled: Arc::new(tokio::sync::Notify::new()),
+        };
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let server_mock = mock.clone();
+        let server = tokio::spawn(async move {
+            rmcp::service::serve_server(server_mock, server_io).await
+        });
+        let handler = mgr.handler_for(namespace, &expose, StderrTail::default());
+        let client = rmcp::service::serve_client(handler, client_io).await.unwrap();
+        let server = server.await.unwrap().unwrap();
+        let server_peer = server.peer().clone();
+        tokio::spawn(server.waiting());
+
+        let mut conn = DownstreamConnection::new(namespace.to_string());
+        conn.mark_healthy(discover_tools(namespace, &expose, client.peer()).await.unwrap());
+        conn.expose = expose;
+        conn.client = Some(client);
+        mgr.downstreams.write().await.push(conn);
+        (mock, server_peer)
+    }
+
+    #[tokio::test]
+    async fn test_manager_expose_filters_listing_and_routing() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(
+            &mgr,
+            "linux",
+            vec!["system.cpu", "service.restart"],
+            vec!["system.*".to_string()],
+        )
+        .await;
+
+        let names: Vec<String> = mgr
+            .list_all_tools()
+            .await
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["linux.system.cpu"]);
+
+        let ok = mgr
+            .route_tool_call("linux.system.cpu", serde_json::json!({}), None)
+            .await
+            .unwrap();
+        assert_eq!(ok.is_error, Some(false));
+
+        let err = mgr
+            .route_tool_call("linux.service.restart", serde_json::json!({}), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_manager_refreshes_tools_on_list_changed() {
+        let mgr = FederationManager::new();
+        let mut events = mgr.subscribe();
+        let (mock, server_peer) =
+            add_mock_downstream(&mgr, "redis", vec!["get"], Vec::new()).await;
+
+        mock.tools.lock().unwrap().push("set");
+        server_peer.notify_tool_list_changed().await.unwrap();
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv())
+            .await
+            .expect("tool list change should be broadcast")
+            .unwrap();
+        assert_eq!(event, FederationEvent::ToolListChanged);
+
+        let names: Vec<String> = mgr
+            .list_all_tools()
+            .await
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["redis.get", "redis.set"]);
+    }
+
+    #[tokio::test]
+    async fn test_manager_broadcasts_namespaced_downstream_logs() {
+        let mgr = FederationManager::new();
+        let mut events = mgr.subscribe();
+        let (_, server_peer) = add_mock_downstream(&mgr, "linux", vec![], Vec::new()).await;
+
+        server_peer
+            .notify_logging_message(rmcp::model::LoggingMessageNotificationParam {
+                level: rmcp::model::LoggingLevel::Warning,
+                logger: Some("mcpd".into()),
+                data: serde_json::json!("disk 91% full"),
+            })
+            .await
+            .unwrap();
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv())
+            .await
+            .expect("log message should be broadcast")
+            .unwrap();
+        let FederationEvent::LogMessage(params) = event else {
+            panic!("expected a log message, got {event:?}");
+        };
+        assert_eq!(params.logger.as_deref(), Some("linux.mcpd"));
+        assert_eq!(params.data, serde_json::json!("disk 91% full"));
+    }
+
+    #[tokio::test]
+    async fn test_manager_federates_resources() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "linux", vec![], Vec::new()).await;
+
+        let resources = mgr.list_all_resources().await;
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].raw.uri, "linux.file:///var/log/syslog");
+
+        let result = mgr.read_resource("linux.file:///var/log/syslog").await.unwrap();
+        let rmcp::model::ResourceContents::TextResourceContents { uri, text, .. } =
+            &result.contents[0]
+        else {
+            panic!("expected text contents");
+        };
+        assert_eq!(uri, "linux.file:///var/log/syslog");
+        assert_eq!(text, "log line");
+
+        let err = mgr.read_resource("redis.key://foo").await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+
+        // The downstream's own error code reaches the upstream
+        let err = mgr.read_resource("linux.file:///missing").await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+        assert_eq!(err.message, "no such file");
+    }
+
+    #[tokio::test]
+    async fn test_manager_federates_prompts() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "ops", vec![], Vec::new()).await;
+
+        let prompts = mgr.list_all_prompts().await;
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].name, "ops.runbook");
+
+        let result = mgr.get_prompt("ops.runbook", None).await.unwrap();
+        // The downstream sees the un-prefixed name
+        assert_eq!(result.description.as_deref(), Some("runbook"));
+
+        let err = mgr.get_prompt("unknown.runbook", None).await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_manager_routes_completions_by_reference() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "ops", vec![], Vec::new()).await;
+
+        let complete = |r#ref| CompleteRequestParams {
+            meta: None,
+            r#ref,
+            argument: rmcp::model::ArgumentInfo {
+                name: "host".into(),
+                value: "web".into(),
+            },
+            context: None,
+        };
+
+        let result = mgr
+            .complete(complete(rmcp::model::Reference::for_prompt("ops.runbook")))
+            .await
+            .unwrap();
+        assert_eq!(result.completion.values, vec!["runbook:web"]);
+
+        let result = mgr
+            .complete(complete(rmcp::model::Reference::for_resource(
+                "ops.file:///var/log/{host}",
+            )))
+            .await
+            .unwrap();
+        assert_eq!(result.completion.values, vec!["file:///var/log/{host}:web"]);
+
+        let err = mgr
+            .complete(complete(rmcp::model::Reference::for_prompt("unknown.runbook")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    struct IdleUpstream;
+    impl rmcp::ServerHandler for IdleUpstream {}
+
+    /// Upstream test client that records the progress notifications it receives
+    /// and answers sampling requests with a canned reply.
+    struct ProgressRecorder(tokio::sync::mpsc::UnboundedSender<rmcp::model::ProgressNotificationParam>);
+
+    impl rmcp::ClientHandler for ProgressRecorder {
+        fn get_info(&self) -> rmcp::model::ClientInfo {
+            rmcp::model::ClientInfo {
+                capabilities: rmcp::model::ClientCapabilities::builder()
+                    .enable_sampling()
+                    .build(),
+                ..Default::default()
+            }
+        }
+
+        async fn create_message(
+            &self,
+            _params: rmcp::model::CreateMessageRequestParams,
+            _context: rmcp::service::RequestContext<RoleClient>,
+        ) -> Result<rmcp::model::CreateMessageResult, rmcp::ErrorData> {
+            Ok(rmcp::model::CreateMessageResult {
+                model: "upstream-model".into(),
+                stop_reason: None,
+                message: rmcp::model::SamplingMessage::assistant_text("all quiet"),
+            })
+        }
+
+        async fn on_progress(
+            &self,
+            params: rmcp::model::ProgressNotificationParam,
+            _context: rmcp::service::NotificationContext<RoleClient>,
+        ) {
+            let _ = self.0.send(params);
+        }
+    }
+
+    /// Open an in-memory upstream session; returns the server-side context and the
+    /// receiver of progress notifications delivered to the upstream client.
+    async fn upstream_session(
+        progress_token: &str,
+        policy: Policy,
+    ) -> (
+        UpstreamContext,
+        tokio::sync::mpsc::UnboundedReceiver<rmcp::model::ProgressNotificationParam>,
+        rmcp::service::RunningService<RoleClient, ProgressRecorder>,
+    ) {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(async move {
+            rmcp::service::serve_server(IdleUpstream, server_io).await
+        });
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = rmcp::service::serve_client(ProgressRecorder(tx), client_io)
+            .await
+            .unwrap();
+        let server = server.await.unwrap().unwrap();
+        let upstream = UpstreamContext {
+            peer: server.peer().clone(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            progress_token: Some(rmcp::model::ProgressToken(
+                rmcp::model::NumberOrString::String(progress_token.to_string().into()),
+            )),
+            ct: tokio_util::sync::CancellationToken::new(),
+            policy: PolicyStore::from(policy),
+            caller: Default::default(),
+            audit: Arc::new(AuditLogger::new("ignore.log")),
+        };
+        tokio::spawn(server.waiting());
+        (upstream, rx, client)
+    }
+
+    #[tokio::test]
+    async fn test_manager_relays_progress_with_upstream_token() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "linux", vec!["progress"], Vec::new()).await;
+        let (upstream, mut progress, _client) = upstream_session("upstream-42", Policy::default()).await;
+
+        mgr.route_tool_call("linux.progress", serde_json::json!({}), Some(upstream))
+            .await
+            .unwrap();
+
+        let relayed = tokio::time::timeout(std::time::Duration::from_secs(5), progress.recv())
+            .await
+            .expect("progress should be relayed upstream")
+            .unwrap();
+        assert_eq!(
+            relayed.progress_token.0,
+            rmcp::model::NumberOrString::String("upstream-42".into())
+        );
+        assert_eq!(relayed.message.as_deref(), Some("halfway"));
+    }
+
+    #[tokio::test]
+    async fn test_manager_cancels_downstream_when_upstream_cancels() {
+        let mgr = Arc::new(FederationManager::new());
+        let (mock, _) = add_mock_downstream(&mgr, "linux", vec!["slow"], Vec::new()).await;
+        let (upstream, mut progress, _client) = upstream_session("upstream-1", Policy::default()).await;
+        let ct = upstream.ct.clone();
+
+        let call = {
+            let mgr = mgr.clone();
+            tokio::spawn(async move {
+                mgr.route_tool_call("linux.slow", serde_json::json!({}), Some(upstream))
+                    .await
+            })
+        };
+
+        // Progress proves the downstream call is running before we cancel
+        progress.recv().await.unwrap();
+        ct.cancel();
+
+        let result = call.await.unwrap();
+        assert!(result.unwrap_err().message.contains("cancelled by upstream"));
+        tokio::time::timeout(std::time::Duration::from_secs(5), mock.cancelled.notified())
+            .await
+            .expect("downstream should observe the cancellation");
+    }
+
+    #[tokio::test]
+    async fn test_manager_relays_sampling_subject_to_policy() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "linux", vec!["sample"], Vec::new()).await;
+        add_mock_downstream(&mgr, "thirdparty", vec!["sample"], Vec::new()).await;
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+
+            [[rules]]
+            id = "allow-linux-sampling"
+            effect = "allow"
+            sampling = ["linux"]
+
+            [[rules]]
+            id = "tighten-sampling"
+            effect = "deny"
+            mode = "audit"
+            sampling = ["*"]
+            "#,
+        )
+        .unwrap();
+        let (mut upstream, _progress, _client) = upstream_session("upstream-7", policy).await;
+        let audit_path = std::env::temp_dir().join(format!("neurond-sampling-{}.log", uuid::Uuid::new_v4()));
+        upstream.audit = Arc::new(AuditLogger::new(audit_path.to_str().unwrap()));
+
+        let allowed = mgr
+            .route_tool_call("linux.sample", serde_json::json!({}), Some(upstream.clone()))
+            .await
+            .unwrap();
+        assert_eq!(allowed.is_error, Some(false));
+        assert_eq!(allowed.content[0].as_text().unwrap().text, "all quiet");
+
+        let denied = mgr
+            .route_tool_call("thirdparty.sample", serde_json::json!({}), Some(upstream))
+            .await
+            .unwrap();
+        assert_eq!(denied.is_error, Some(true));
+        assert!(denied.content[0].as_text().unwrap().text.contains("Access denied to sampling"));
+
+        // The candidate policy would have denied the allowed request only
+        let log = std::fs::read_to_string(&audit_path).unwrap();
+        let shadows: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|event| event["method"] == "policy/shadow")
+            .collect();
+        assert_eq!(shadows.len(), 1);
+        assert_eq!(shadows[0]["tool"], "linux");
+        assert_eq!(shadows[0]["decision"], "would_deny");
+        assert_eq!(shadows[0]["params"]["method"], "sampling/createMessage");
+        let _ = std::fs::remove_file(audit_path);
+    }
+
+    #[tokio::test]
+    async fn test_manager_rejects_sampling_outside_tool_call() {
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "linux", vec!["sample"], Vec::new()).await;
+
+        let result = mgr
+            .route_tool_call("linux.sample", serde_json::json!({}), None)
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.content[0].as_text().unwrap().text.contains("only available while a tool call"));
+    }
+
+    #[tokio::test]
+    async fn test_manager_routes_sampling_only_to_the_originating_session() {
+        let mgr = Arc::new(FederationManager::new());
+        add_mock_downstream(&mgr, "linux", vec!["sample", "slow"], Vec::new()).await;
+        let policy = || -> Policy {
+            toml::from_str(
+                r#"
+                default_action = "allow"
+                rules = [{ id = "sampling", effect = "allow", sampling = ["linux"] }]
+                "#,
+            )
+            .unwrap()
+        };
+        let (other, mut other_progress, _other_client) = upstream_session("other", policy()).await;
+        let (upstream, _progress, _client) = upstream_session("upstream", policy()).await;
+
+        // Another session's call is in flight on the same downstream
+        let ct = other.ct.clone();
+        let slow = {
+            let mgr = mgr.clone();
+            tokio::spawn(async move { mgr.route_tool_call("linux.slow", serde_json::json!({}), Some(other)).await })
+        };
+        other_progress.recv().await.unwrap();
+
+        let ambiguous = mgr
+            .route_tool_call("linux.sample", serde_json::json!({}), Some(upstream.clone()))
+            .await
+            .unwrap();
+        assert_eq!(ambiguous.is_error, Some(true));
+        assert!(ambiguous.content[0].as_text().unwrap().text.contains("is ambiguous"));
+
+        let correlated = mgr
+            .route_tool_call("linux.sample", serde_json::json!({"echo_call_id": true}), Some(upstream))
+            .await
+            .unwrap();
+        assert_eq!(correlated.is_error, Some(false));
+        assert_eq!(correlated.content[0].as_text().unwrap().text, "all quiet");
+
+        ct.cancel();
+        let _ = slow.await;
+    }
+
+    #[tokio::test]
+    async fn test_manager_lazy_downstream_advertises_catalog_until_started() {
+        let dir = std::env::temp_dir().join(format!("neurond-catalog-{}", uuid::Uuid::new_v4()));
+        fixture::save_catalogs(&dir, &[("heavy", &["heavy.scan"])]);
+        let config: FederationConfig = toml::from_str(&format!(
+            r#"
+            catalog_dir = "{}"
+
+            [[servers]]
+            namespace = "heavy"
+            transport = "stdio"
+            command = "/bin/sh"
+            args = ["-c", "echo 'model not found' >&2; exit 1"]
+            start = "lazy"
+            expose = ["scan"]
+            "#,
+            dir.display()
+        ))
+        .unwrap();
+
+        let mgr = FederationManager::new();
+        mgr.init_from_config(&config).await.unwrap();
+        let names: Vec<String> = mgr.list_all_tools().await.into_iter().map(|t| t.name.to_string()).collect();
+        assert_eq!(names, vec!["heavy.scan"]);
+        assert_eq!(mgr.status_summary().await, vec![("heavy".to_string(), "dormant".to_string())]);
+
+        // A tool hidden by `expose` is unknown without starting the child
+        let err = mgr
+            .route_tool_call("heavy.wipe", serde_json::json!({}), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::METHOD_NOT_FOUND);
+        assert!(mgr.status().await[0].stderr.is_empty());
+
+        // The first call spawns the child; a failed start leaves it dormant for the next call
+        let err = mgr
+            .route_tool_call("heavy.scan", serde_json::json!({}), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INTERNAL_ERROR);
+        assert!(err.message.contains("Failed to start downstream 'heavy'"), "{}", err.message);
+        assert_eq!(mgr.status_summary().await, vec![("heavy".to_string(), "dormant".to_string())]);
+        assert_eq!(mgr.list_all_tools().await.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_manager_stops_idle_downstream() {
+        let dir = std::env::temp_dir().join(format!("neurond-catalog-{}", uuid::Uuid::new_v4()));
+        let mgr = FederationManager::new();
+        add_mock_downstream(&mgr, "redis", vec!["get"], Vec::new()).await;
+        let config: DownstreamServer = toml::from_str(
+            r#"
+            namespace = "redis"
+            transport = "stdio"
+            command = "/bin/false"
+            healthcheck_interval_secs = 1
+            idle_timeout_secs = 0
+            "#,
+        )
+        .unwrap();
+
+        let handler = {
+            let mut downstreams = mgr.downstreams.write().await;
+            let conn = &mut downstreams[0];
+            let handler = conn.client.as_ref().unwrap().service().clone();
+            conn.lazy = Some(LazyStart::new(config.clone(), handler.clone(), dir.clone()));
+            handler
+        };
+        lifecycle::spawn_monitor(
+            mgr.downstreams.clone(),
+            mgr.events.clone(),
+            config,
+            handler,
+            Default::default(),
+        );
+
+        let dormant = async {
+            while mgr.status_summary().await[0].1 != "dormant" {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(5), dormant)
+            .await
+            .expect("idle downstream should be stopped");
+
+        // Still advertised, and cached for the next boot
+        assert_eq!(mgr.list_all_tools().await.len(), 1);
+        let cached = catalog::load(&dir, "redis", &[]).unwrap();
+        assert_eq!(cached[0].name, "redis.get");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_manager_reload_diffs_downstreams() {
+        let dir = std::env::temp_dir().join(format!("neurond-catalog-{}", uuid::Uuid::new_v4()));
+        // Lazy downstreams with catalogs register Dormant without spawning anything
+        fixture::save_catalogs(&dir, &[("a", &["a.x"]), ("b", &["b.x", "b.y"]), ("c", &["c.x"])]);
+        let config = |servers: &[(&str, &[&str])]| fixture::lazy_config(&dir, servers);
+
+        let mgr = FederationManager::new();
+        mgr.init_from_config(&config(&[("a", &[]), ("b", &[])])).await.unwrap();
+        let mut events = mgr.subscribe();
+
+        let next = config(&[("b", &["x"]), ("c", &[])]);
+        let summary = mgr.reload(&next).await;
+        assert_eq!(
+            summary,
+            ReloadSummary {
+                added: vec!["c".to_string()],
+                removed: vec!["a".to_string()],
+                changed: vec!["b".to_string()],
+            }
+        );
+        assert_eq!(mgr.namespaces().await, vec!["b", "c"]);
+        let names: Vec<String> = mgr.list_all_tools().await.into_iter().map(|t| t.name.to_string()).collect();
+        assert_eq!(names, vec!["b.x", "c.x"]);
+        assert_eq!(events.try_recv().unwrap(), FederationEvent::ToolListChanged);
+
+        assert!(mgr.reload(&next).await.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_manager_reload_drains_removed_downstream() {
+        let mgr = Arc::new(FederationManager::new());
+        let (mock, _server_peer) = add_mock_downstream(&mgr, "redis", vec!["slow"], Vec::new()).await;
+
+        let call = {
+            let mgr = mgr.clone();
+            tokio::spawn(async move {
+                mgr.route_tool_call("redis.slow", serde_json::json!({}), None).await
+            })
+        };
+        let handler = mgr.downstreams.read().await[0].client.as_ref().unwrap().service().clone();
+        while handler.idle_for().is_some() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let summary = mgr.reload(&FederationConfig::default()).await;
+        assert_eq!(summary.removed, vec!["redis"]);
+        assert!(mgr.namespaces().await.is_empty());
+
+        // The in-flight call keeps running while the downstream drains
+        let cancelled = tokio::time::timeout(std::time::Duration::from_millis(300), mock.cancelled.notified()).await;
+        assert!(cancelled.is_err());
+        assert!(!call.is_finished());
+    }
+
     #[tokio::test]
     async fn test_manager_init_empty_config() {
         let mgr = FederationManager::new();
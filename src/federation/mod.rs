@@ -1,4 +1,11 @@
+pub mod catalog;
 pub mod connection;
+pub mod events;
+pub mod handler;
+pub mod http_client;
+pub mod lifecycle;
 pub mod manager;
 pub mod namespace;
+pub mod oauth;
+pub mod sandbox;
 pub mod transport;
@@ -1,4 +1,7 @@
-use rmcp::model::Tool;
+use rmcp::model::{
+    Prompt, PromptReference, Reference, Resource, ResourceContents, ResourceReference,
+    ResourceTemplate, Tool,
+};
 
 /// Apply namespace prefix to a tool name.
 ///
@@ -20,11 +23,40 @@ pub fn strip_namespace(namespace: &str, prefixed_name: &str) -> Option<String> {
     }
 }
 
-/// Apply namespace prefix to all tools from a downstream.
-/// Returns a new vec of tools with prefixed names.
-pub fn namespace_tools(namespace: &str, tools: &[Tool]) -> Vec<Tool> {
+/// Check whether a downstream tool name passes the `expose` allowlist.
+///
+/// Patterns are globs matched against the original (un-prefixed) tool name.
+/// An empty allowlist exposes every tool.
+pub fn is_exposed(expose: &[String], tool_name: &str) -> bool {
+    expose.is_empty()
+        || expose.iter().any(|pattern| {
+            glob::Pattern::new(pattern)
+                .map(|p| p.matches(tool_name))
+                .unwrap_or(false)
+        })
+}
+
+/// Return the `expose` patterns that match none of the given tools.
+///
+/// Used at connect time to warn about typos or stale allowlist entries.
+pub fn unmatched_expose_patterns<'a>(expose: &'a [String], tools: &[Tool]) -> Vec<&'a str> {
+    expose
+        .iter()
+        .filter(|pattern| {
+            !tools
+                .iter()
+                .any(|tool| is_exposed(std::slice::from_ref(*pattern), &tool.name))
+        })
+        .map(|s| s.as_str())
+        .collect()
+}
+
+/// Apply namespace prefix to all exposed tools from a downstream.
+/// Returns a new vec of tools with prefixed names, omitting tools hidden by `expose`.
+pub fn namespace_tools(namespace: &str, tools: &[Tool], expose: &[String]) -> Vec<Tool> {
     tools
         .iter()
+        .filter(|tool| is_exposed(expose, &tool.name))
         .map(|tool| {
             let mut namespaced = tool.clone();
             namespaced.name = prefix_tool_name(namespace, &tool.name).into();
@@ -33,6 +65,89 @@ pub fn namespace_tools(namespace: &str, tools: &[Tool]) -> Vec<Tool> {
         .collect()
 }
 
+/// Apply namespace prefix to all prompts from a downstream.
+pub fn namespace_prompts(namespace: &str, prompts: &[Prompt]) -> Vec<Prompt> {
+    prompts
+        .iter()
+        .map(|prompt| {
+            let mut namespaced = prompt.clone();
+            namespaced.name = prefix_tool_name(namespace, &prompt.name);
+            namespaced
+        })
+        .collect()
+}
+
+/// Apply namespace prefix to a resource URI or URI template.
+///
+/// The namespace is joined to the URI scheme the same way tool names are prefixed:
+/// "file:///var/log/syslog" becomes "linux.file:///var/log/syslog", which is still
+/// a valid URI and is reversed with [`resolve_namespace`].
+pub fn prefix_uri(namespace: &str, uri: &str) -> String {
+    prefix_tool_name(namespace, uri)
+}
+
+/// Apply namespace prefix to all resources from a downstream.
+pub fn namespace_resources(namespace: &str, resources: &[Resource]) -> Vec<Resource> {
+    resources
+        .iter()
+        .map(|resource| {
+            let mut namespaced = resource.clone();
+            namespaced.raw.uri = prefix_uri(namespace, &resource.raw.uri);
+            namespaced
+        })
+        .collect()
+}
+
+/// Apply namespace prefix to all resource templates from a downstream.
+pub fn namespace_resource_templates(
+    namespace: &str,
+    templates: &[ResourceTemplate],
+) -> Vec<ResourceTemplate> {
+    templates
+        .iter()
+        .map(|template| {
+            let mut namespaced = template.clone();
+            namespaced.raw.uri_template = prefix_uri(namespace, &template.raw.uri_template);
+            namespaced
+        })
+        .collect()
+}
+
+/// Re-apply the namespace prefix to the URIs of resource contents returned by a read.
+pub fn namespace_resource_contents(
+    namespace: &str,
+    contents: Vec<ResourceContents>,
+) -> Vec<ResourceContents> {
+    contents
+        .into_iter()
+        .map(|mut content| {
+            let (ResourceContents::TextResourceContents { uri, .. }
+            | ResourceContents::BlobResourceContents { uri, .. }) = &mut content;
+            *uri = prefix_uri(namespace, uri);
+            content
+        })
+        .collect()
+}
+
+/// The namespaced prompt name or resource template URI a completion refers to.
+pub fn reference_target(reference: &Reference) -> &str {
+    match reference {
+        Reference::Prompt(prompt) => &prompt.name,
+        Reference::Resource(resource) => &resource.uri,
+    }
+}
+
+/// Point a completion reference at a different name or URI, keeping its kind.
+pub fn retarget_reference(reference: Reference, target: String) -> Reference {
+    match reference {
+        Reference::Prompt(prompt) => Reference::Prompt(PromptReference {
+            name: target,
+            ..prompt
+        }),
+        Reference::Resource(_) => Reference::Resource(ResourceReference { uri: target }),
+    }
+}
+
 /// Resolve which downstream namespace a tool call belongs to.
 ///
 /// Given a list of known namespaces and a tool name like "linux.system.cpu",
@@ -58,6 +173,24 @@ pub fn resolve_namespace<'a>(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_completion_reference_round_trip() {
+        let namespaces = vec!["ops".to_string(), "linux".to_string()];
+
+        let prompt = Reference::for_prompt("ops.runbook");
+        assert_eq!(reference_target(&prompt), "ops.runbook");
+        let (ns, original) = resolve_namespace(&namespaces, reference_target(&prompt)).unwrap();
+        assert_eq!(ns, "ops");
+        assert_eq!(retarget_reference(prompt, original), Reference::for_prompt("runbook"));
+
+        let template = Reference::for_resource("linux.file:///var/log/{name}");
+        let (_, original) = resolve_namespace(&namespaces, reference_target(&template)).unwrap();
+        assert_eq!(
+            retarget_reference(template, original),
+            Reference::for_resource("file:///var/log/{name}")
+        );
+    }
+
     #[test]
     fn test_prefix_tool_name() {
         assert_eq!(prefix_tool_name("linux", "system.cpu"), "linux.system.cpu");
@@ -74,6 +207,20 @@ mod tests {
         assert_eq!(strip_namespace("linux", "linux"), None); // no trailing dot
     }
 
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string().into(),
+            title: None,
+            description: None,
+            input_schema: serde_json::json!({"type": "object"}).as_object().unwrap().clone().into(),
+            output_schema: None,
+            annotations: None,
+            icons: None,
+            meta: None,
+            execution: None,
+        }
+    }
+
     #[test]
     fn test_namespace_tools() {
         let tools = vec![Tool {
@@ -87,10 +234,84 @@ mod tests {
             meta: None,
             execution: None,
         }];
-        let namespaced = namespace_tools("linux", &tools);
+        let namespaced = namespace_tools("linux", &tools, &[]);
         assert_eq!(namespaced[0].name.as_ref(), "linux.system.cpu");
     }
 
+    #[test]
+    fn test_namespace_tools_applies_expose() {
+        let tools = vec![tool("system.cpu"), tool("system.memory"), tool("service.restart")];
+        let expose = vec!["system.*".to_string()];
+        let namespaced = namespace_tools("linux", &tools, &expose);
+        let names: Vec<&str> = namespaced.iter().map(|t| t.name.as_ref()).collect();
+        assert_eq!(names, vec!["linux.system.cpu", "linux.system.memory"]);
+    }
+
+    #[test]
+    fn test_is_exposed() {
+        assert!(is_exposed(&[], "anything"));
+        let expose = vec!["get".to_string(), "key?".to_string()];
+        assert!(is_exposed(&expose, "get"));
+        assert!(is_exposed(&expose, "keys"));
+        assert!(!is_exposed(&expose, "flushall"));
+    }
+
+    #[test]
+    fn test_unmatched_expose_patterns() {
+        let tools = vec![tool("get"), tool("set")];
+        let expose = vec!["get".to_string(), "flush*".to_string()];
+        assert_eq!(unmatched_expose_patterns(&expose, &tools), vec!["flush*"]);
+    }
+
+    #[test]
+    fn test_namespace_prompts() {
+        let prompts = vec![Prompt::new("disk-full-runbook", Some("Triage a full disk"), None)];
+        let namespaced = namespace_prompts("linux", &prompts);
+        assert_eq!(namespaced[0].name, "linux.disk-full-runbook");
+        assert_eq!(namespaced[0].description.as_deref(), Some("Triage a full disk"));
+    }
+
+    #[test]
+    fn test_resource_uri_round_trip() {
+        let resources = vec![Resource::new(
+            rmcp::model::RawResource::new("file:///var/log/syslog", "syslog"),
+            None,
+        )];
+        let namespaced = namespace_resources("linux", &resources);
+        assert_eq!(namespaced[0].raw.uri, "linux.file:///var/log/syslog");
+
+        let namespaces = vec!["linux".to_string()];
+        let (ns, original) = resolve_namespace(&namespaces, &namespaced[0].raw.uri).unwrap();
+        assert_eq!(ns, "linux");
+        assert_eq!(original, "file:///var/log/syslog");
+
+        let contents = namespace_resource_contents(
+            "linux",
+            vec![ResourceContents::text("hello", "file:///var/log/syslog")],
+        );
+        let ResourceContents::TextResourceContents { uri, .. } = &contents[0] else {
+            panic!("expected text contents");
+        };
+        assert_eq!(uri, "linux.file:///var/log/syslog");
+    }
+
+    #[test]
+    fn test_namespace_resource_templates() {
+        let templates = vec![ResourceTemplate::new(
+            rmcp::model::RawResourceTemplate {
+                uri_template: "file:///var/log/{name}".into(),
+                name: "log".into(),
+                title: None,
+                description: None,
+                mime_type: None,
+                icons: None,
+            },
+            None,
+        )];
+        let namespaced = namespace_resource_templates("linux", &templates);
+        assert_eq!(namespaced[0].raw.uri_template, "linux.file:///var/log/{name}");
+    }
+
     #[test]
     fn test_resolve_namespace() {
         let namespaces = vec!["linux".to_string(), "redis".to_string()];
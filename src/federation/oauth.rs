@@ -0,0 +1,341 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use reqwest::Url;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::config::OAuthClientCredentials;
+
+/// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// OAuth 2.1 client-credentials token source for one HTTP downstream.
+///
+/// Uses the configured token endpoint or authorization server, or else discovers
+/// the authorization server from the downstream's protected-resource metadata
+/// (RFC 9728) the first time it answers 401. The issued access token is cached
+/// until shortly before it expires. The client secret is only sent over HTTPS.
+pub struct OAuthClient {
+    http: reqwest::Client,
+    /// URL of the downstream MCP endpoint (the protected resource)
+    resource: String,
+    credentials: OAuthClientCredentials,
+    state: Mutex<OAuthState>,
+}
+
+#[derive(Default)]
+struct OAuthState {
+    token_endpoint: Option<String>,
+    token: Option<CachedToken>,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self, now: Instant) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| now + REFRESH_MARGIN < expires_at)
+    }
+}
+
+#[derive(Deserialize)]
+struct ProtectedResourceMetadata {
+    resource: String,
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AuthorizationServerMetadata {
+    issuer: String,
+    token_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl OAuthClient {
+    pub fn new(http: reqwest::Client, resource: &str, credentials: OAuthClientCredentials) -> Self {
+        let state = OAuthState {
+            token_endpoint: credentials.token_endpoint.clone(),
+            token: None,
+        };
+        Self {
+            http,
+            resource: resource.to_string(),
+            credentials,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// The cached access token, refreshed if it is about to expire.
+    ///
+    /// Returns None until the downstream has challenged us once, since the
+    /// authorization server is only known after discovery.
+    pub async fn current_token(&self) -> anyhow::Result<Option<String>> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = state.token.as_ref().filter(|t| t.is_fresh(Instant::now())) {
+            return Ok(Some(token.access_token.clone()));
+        }
+        let Some(token_endpoint) = state.token_endpoint.clone() else {
+            return Ok(None);
+        };
+        let token = self.request_token(&token_endpoint).await?;
+        let access_token = token.access_token.clone();
+        state.token = Some(token);
+        Ok(Some(access_token))
+    }
+
+    /// Obtain a new token after the downstream rejected the current one.
+    ///
+    /// `challenge` is the downstream's `WWW-Authenticate` header, which may point
+    /// at its protected-resource metadata.
+    pub async fn authorize(&self, challenge: Option<&str>) -> anyhow::Result<String> {
+        let mut state = self.state.lock().await;
+        state.token = None;
+
+        let token_endpoint = match state.token_endpoint.clone() {
+            Some(endpoint) => endpoint,
+            None => {
+                let endpoint = self.discover_token_endpoint(challenge).await?;
+                state.token_endpoint = Some(endpoint.clone());
+                endpoint
+            }
+        };
+
+        let token = self.request_token(&token_endpoint).await?;
+        let access_token = token.access_token.clone();
+        state.token = Some(token);
+        Ok(access_token)
+    }
+
+    /// Resource metadata → authorization server metadata → token endpoint.
+    ///
+    /// A configured issuer skips the resource metadata. Metadata must describe
+    /// the URL it was fetched for (RFC 9728 §3.3, RFC 8414 §3.3). The token
+    /// endpoint of a discovered issuer must be on the issuer's origin, so
+    /// tampered metadata cannot send the client secret elsewhere.
+    async fn discover_token_endpoint(&self, challenge: Option<&str>) -> anyhow::Result<String> {
+        let issuer = match &self.credentials.issuer {
+            Some(issuer) => issuer.clone(),
+            None => {
+                let metadata_url = match challenge.and_then(|c| challenge_param(c, "resource_metadata")) {
+                    Some(url) => url,
+                    None => well_known(&self.resource, "oauth-protected-resource")?,
+                };
+                let resource: ProtectedResourceMetadata = self.get_json(&metadata_url).await?;
+                if !same_url(&resource.resource, &self.resource) {
+                    anyhow::bail!(
+                        "{} describes resource {}, not {}",
+                        metadata_url,
+                        resource.resource,
+                        self.resource
+                    );
+                }
+                resource
+                    .authorization_servers
+                    .into_iter()
+                    .next()
+                    .with_context(|| format!("No authorization server listed in {}", metadata_url))?
+            }
+        };
+
+        let server: AuthorizationServerMetadata = self
+            .get_json(&well_known(&issuer, "oauth-authorization-server")?)
+            .await?;
+        if !same_url(&server.issuer, &issuer) {
+            anyhow::bail!("Authorization server {} reports issuer {}", issuer, server.issuer);
+        }
+        if self.credentials.issuer.is_none() && !same_origin(&server.token_endpoint, &issuer) {
+            anyhow::bail!(
+                "Token endpoint {} is not on the origin of issuer {}; configure `issuer` or `token_endpoint` to trust it",
+                server.token_endpoint,
+                issuer
+            );
+        }
+        tracing::info!(
+            resource = %self.resource,
+            token_endpoint = %server.token_endpoint,
+            "Discovered OAuth authorization server"
+        );
+        Ok(server.token_endpoint)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> anyhow::Result<T> {
+        require_https(url)?;
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to fetch OAuth metadata: {}", url))?
+            .json()
+            .await
+            .with_context(|| format!("Invalid OAuth metadata: {}", url))
+    }
+
+    /// Client-credentials grant, authenticating with HTTP Basic (client_secret_basic).
+    async fn request_token(&self, token_endpoint: &str) -> anyhow::Result<CachedToken> {
+        require_https(token_endpoint)?;
+        let secret_file = &self.credentials.client_secret_file;
+        let secret = std::fs::read_to_string(secret_file)
+            .with_context(|| format!("Failed to read client secret file: {}", secret_file))?;
+
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("resource", self.resource.as_str()),
+        ];
+        if let Some(scope) = &self.credentials.scope {
+            form.push(("scope", scope.as_str()));
+        }
+
+        let issued_at = Instant::now();
+        let response: TokenResponse = self
+            .http
+            .post(token_endpoint)
+            .basic_auth(&self.credentials.client_id, Some(secret.trim()))
+            .form(&form)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Token request failed: {}", token_endpoint))?
+            .json()
+            .await
+            .with_context(|| format!("Invalid token response from {}", token_endpoint))?;
+
+        tracing::debug!(resource = %self.resource, expires_in = ?response.expires_in, "Obtained OAuth access token");
+        Ok(CachedToken {
+            access_token: response.access_token,
+            expires_at: response
+                .expires_in
+                .map(|secs| issued_at + Duration::from_secs(secs)),
+        })
+    }
+}
+
+/// Refuse OAuth endpoints without TLS. Plain HTTP is allowed on loopback only,
+/// where there is no network to eavesdrop on.
+fn require_https(url: &str) -> anyhow::Result<()> {
+    let parsed = Url::parse(url).with_context(|| format!("Invalid URL: {}", url))?;
+    let loopback = match parsed.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    };
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => anyhow::bail!("Refusing OAuth endpoint without HTTPS: {}", url),
+    }
+}
+
+/// Whether two URLs are the same once parsed, e.g. regardless of host case.
+fn same_url(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether two URLs share scheme, host and port.
+fn same_origin(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => false,
+    }
+}
+
+/// Extract a quoted or bare parameter from a `WWW-Authenticate` challenge.
+fn challenge_param(challenge: &str, name: &str) -> Option<String> {
+    let key = format!("{}=", name);
+    let start = challenge.find(&key)? + key.len();
+    let rest = &challenge[start..];
+    let value = match rest.strip_prefix('"') {
+        Some(quoted) => &quoted[..quoted.find('"')?],
+        None => rest.split([',', ' ']).next()?,
+    };
+    Some(value.to_string())
+}
+
+/// Well-known metadata URL for `base`, inserting `/.well-known/<suffix>` before its path.
+fn well_known(base: &str, suffix: &str) -> anyhow::Result<String> {
+    let url = Url::parse(base).with_context(|| format!("Invalid URL: {}", base))?;
+    let path = url.path().trim_end_matches('/');
+    Ok(format!(
+        "{}/.well-known/{}{}",
+        url.origin().ascii_serialization(),
+        suffix,
+        path
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_param() {
+        let challenge = r#"Bearer error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource/mcp""#;
+        assert_eq!(
+            challenge_param(challenge, "resource_metadata").as_deref(),
+            Some("https://mcp.example.com/.well-known/oauth-protected-resource/mcp")
+        );
+        assert_eq!(challenge_param("Bearer realm=mcp, scope=read", "realm").as_deref(), Some("mcp"));
+        assert_eq!(challenge_param("Bearer", "resource_metadata"), None);
+    }
+
+    #[test]
+    fn test_well_known_inserts_before_path() {
+        assert_eq!(
+            well_known("https://mcp.example.com/mcp", "oauth-protected-resource").unwrap(),
+            "https://mcp.example.com/.well-known/oauth-protected-resource/mcp"
+        );
+        assert_eq!(
+            well_known("https://auth.example.com/", "oauth-authorization-server").unwrap(),
+            "https://auth.example.com/.well-known/oauth-authorization-server"
+        );
+    }
+
+    #[test]
+    fn test_require_https() {
+        assert!(require_https("https://auth.example.com/token").is_ok());
+        assert!(require_https("http://127.0.0.1:8080/token").is_ok());
+        assert!(require_https("http://[::1]/token").is_ok());
+        assert!(require_https("http://localhost/token").is_ok());
+        assert!(require_https("http://auth.example.com/token").is_err());
+        assert!(require_https("http://127.0.0.1.example.com/token").is_err());
+        assert!(require_https("ftp://auth.example.com/token").is_err());
+    }
+
+    #[test]
+    fn test_same_origin() {
+        assert!(same_origin("https://auth.example.com/token", "https://AUTH.example.com:443/tenant"));
+        assert!(!same_origin("https://evil.example.com/token", "https://auth.example.com/"));
+        assert!(!same_origin("https://auth.example.com:8443/token", "https://auth.example.com/"));
+        assert!(!same_origin("http://auth.example.com/token", "https://auth.example.com/"));
+    }
+
+    #[test]
+    fn test_token_refreshes_before_expiry() {
+        let now = Instant::now();
+        let token = |expires_in: Option<u64>| CachedToken {
+            access_token: "t".to_string(),
+            expires_at: expires_in.map(|s| now + Duration::from_secs(s)),
+        };
+        assert!(token(Some(3600)).is_fresh(now));
+        assert!(!token(Some(10)).is_fresh(now));
+        assert!(token(None).is_fresh(now));
+    }
+}
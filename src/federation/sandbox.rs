@@ -0,0 +1,220 @@
+use std::ffi::CString;
+use std::io;
+
+use anyhow::Context;
+
+use crate::config::StdioSandbox;
+
+/// Apply a sandbox to a stdio downstream's command before it is spawned.
+///
+/// Names are resolved and strings allocated here, in the parent. The `pre_exec`
+/// hook then runs in the forked child, in this order: umask, rlimits, namespaces
+/// and /tmp (which need neurond's privileges), dropping to the run-as group and
+/// user, and finally PR_SET_NO_NEW_PRIVS. Call this before adding the configured
+/// `env`, since `env_clear` would otherwise discard it.
+pub fn apply(cmd: &mut tokio::process::Command, sandbox: &StdioSandbox) -> anyhow::Result<()> {
+    apply_with_env(cmd, sandbox, std::env::vars())
+}
+
+/// [`apply`], with `inherited` standing in for neurond's environment.
+fn apply_with_env(
+    cmd: &mut tokio::process::Command,
+    sandbox: &StdioSandbox,
+    inherited: impl IntoIterator<Item = (String, String)>,
+) -> anyhow::Result<()> {
+    let uid = sandbox.user.as_deref().map(resolve_user).transpose()?;
+    let gid = match (&sandbox.group, uid) {
+        (Some(group), _) => Some(resolve_group(group)?),
+        (None, Some((_, primary_gid))) => Some(primary_gid),
+        (None, None) => None,
+    };
+    let uid = uid.map(|(uid, _)| uid);
+
+    if let Some(cwd) = &sandbox.cwd {
+        cmd.current_dir(cwd);
+    }
+    if sandbox.env_clear {
+        cmd.env_clear();
+        cmd.envs(inherited.into_iter().filter(|(name, _)| sandbox.env_allowlist.contains(name)));
+    }
+
+    let umask = sandbox.umask;
+    let limits = [
+        (libc::RLIMIT_AS, sandbox.memory_bytes),
+        (libc::RLIMIT_NOFILE, sandbox.nofile),
+        (libc::RLIMIT_NPROC, sandbox.nproc),
+    ];
+    let mut unshare_flags = 0;
+    if sandbox.no_network {
+        unshare_flags |= libc::CLONE_NEWNET;
+    }
+    if sandbox.private_tmp {
+        unshare_flags |= libc::CLONE_NEWNS;
+    }
+    let private_tmp = sandbox.private_tmp;
+    let no_new_privs = !sandbox.allow_new_privileges;
+    let (root, tmp, tmpfs, tmpfs_opts) = (c("/"), c("/tmp"), c("tmpfs"), c("mode=1777"));
+
+    // SAFETY: the closure only makes async-signal-safe syscalls on data prepared above.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(mask) = umask {
+                libc::umask(mask as libc::mode_t);
+            }
+            for (resource, limit) in limits {
+                if let Some(limit) = limit {
+                    let rlim = libc::rlimit {
+                        rlim_cur: limit as libc::rlim_t,
+                        rlim_max: limit as libc::rlim_t,
+                    };
+                    check(libc::setrlimit(resource, &rlim))?;
+                }
+            }
+            if unshare_flags != 0 {
+                check(libc::unshare(unshare_flags))?;
+            }
+            if private_tmp {
+                // Keep the new /tmp from propagating back into neurond's namespace
+                check(libc::mount(
+                    std::ptr::null(),
+                    root.as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ))?;
+                check(libc::mount(
+                    tmpfs.as_ptr(),
+                    tmp.as_ptr(),
+                    tmpfs.as_ptr(),
+                    libc::MS_NOSUID | libc::MS_NODEV,
+                    tmpfs_opts.as_ptr().cast(),
+                ))?;
+            }
+            if let Some(gid) = gid {
+                check(libc::setgroups(0, std::ptr::null()))?;
+                check(libc::setgid(gid))?;
+            }
+            if let Some(uid) = uid {
+                check(libc::setuid(uid))?;
+            }
+            if no_new_privs {
+                check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+fn c(s: &str) -> CString {
+    CString::new(s).expect("static path contains no NUL")
+}
+
+fn check(ret: libc::c_int) -> io::Result<()> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Resolve a user name or numeric uid to (uid, primary gid).
+fn resolve_user(user: &str) -> anyhow::Result<(libc::uid_t, libc::gid_t)> {
+    let name = CString::new(user).context("User name contains NUL")?;
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: passwd is zero-initialisable; getpwnam_r/getpwuid_r write into buf only.
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let ret = unsafe {
+        match user.parse::<libc::uid_t>() {
+            Ok(uid) => libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result),
+            Err(_) => libc::getpwnam_r(name.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result),
+        }
+    };
+    if ret != 0 || result.is_null() {
+        anyhow::bail!("Unknown sandbox user: {}", user);
+    }
+    Ok((pwd.pw_uid, pwd.pw_gid))
+}
+
+/// Resolve a group name or numeric gid.
+fn resolve_group(group: &str) -> anyhow::Result<libc::gid_t> {
+    if let Ok(gid) = group.parse::<libc::gid_t>() {
+        return Ok(gid);
+    }
+    let name = CString::new(group).context("Group name contains NUL")?;
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: group is zero-initialisable; getgrnam_r writes into buf only.
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let ret = unsafe {
+        libc::getgrnam_r(name.as_ptr(), &mut grp, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if ret != 0 || result.is_null() {
+        anyhow::bail!("Unknown sandbox group: {}", group);
+    }
+    Ok(grp.gr_gid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_user_and_group() {
+        assert_eq!(resolve_user("root").unwrap(), (0, 0));
+        assert_eq!(resolve_user("0").unwrap().0, 0);
+        assert_eq!(resolve_group("0").unwrap(), 0);
+        assert!(resolve_user("no-such-user-neurond").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_restricts_env_umask_and_rlimits() {
+        let inherited = [("NEUROND_SANDBOX_KEEP", "kept"), ("NEUROND_SANDBOX_DROP", "leaked")]
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+        let sandbox = StdioSandbox {
+            cwd: Some("/".to_string()),
+            env_clear: true,
+            env_allowlist: vec!["NEUROND_SANDBOX_KEEP".to_string()],
+            umask: Some(0o077),
+            nofile: Some(64),
+            ..Default::default()
+        };
+
+        let mut cmd = tokio::process::Command::new("/bin/sh");
+        cmd.args([
+            "-c",
+            r#"echo "$NEUROND_SANDBOX_KEEP|$NEUROND_SANDBOX_DROP|$(umask)|$(ulimit -n)|$(pwd)|$(grep NoNewPrivs /proc/self/status | cut -f2)""#,
+        ]);
+        apply_with_env(&mut cmd, &sandbox, inherited).unwrap();
+        let output = cmd.output().await.unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "kept||0077|64|/|1");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs root for namespaces and setuid; run with --ignored"]
+    async fn test_apply_namespaces_and_drops_privileges() {
+        let marker = std::env::temp_dir().join(format!("neurond-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&marker, "").unwrap();
+        let sandbox = StdioSandbox {
+            user: Some("nobody".to_string()),
+            no_network: true,
+            private_tmp: true,
+            ..Default::default()
+        };
+
+        let mut cmd = tokio::process::Command::new("/bin/sh");
+        cmd.args([
+            "-c",
+            // /proc/net/dev lists only the header lines and `lo` in a fresh network namespace
+            r#"echo "$(id -u)|$(ls -A /tmp | wc -l)|$(wc -l < /proc/net/dev)""#,
+        ]);
+        apply(&mut cmd, &sandbox).unwrap();
+        let output = cmd.output().await.unwrap();
+        let _ = std::fs::remove_file(marker);
+
+        let (nobody, _) = resolve_user("nobody").unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), format!("{nobody}|0|3"));
+    }
+}
@@ -1,19 +1,26 @@
 use anyhow::Context;
-use rmcp::service::RunningService;
-use rmcp::RoleClient;
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
 use rmcp::transport::StreamableHttpClientTransport;
-use crate::config::DownstreamTransport;
+use crate::config::{DownstreamTransport, RemoteAuth, StdioSandbox};
+use crate::federation::connection::StderrTail;
+use crate::federation::handler::{DownstreamClient, DownstreamHandler};
+use crate::federation::http_client::HttpClient;
+use crate::federation::oauth::OAuthClient;
+use crate::federation::sandbox;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio_util::sync::{CancellationToken, DropGuard};
 
 /// Connect to a downstream MCP server via Streamable HTTP (localhost transport).
 ///
 /// The downstream server must already be running and listening on the given URL.
 pub async fn connect_localhost(
     url: &str,
-) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    handler: DownstreamHandler,
+) -> anyhow::Result<DownstreamClient> {
     let transport = StreamableHttpClientTransport::from_uri(url);
 
-    let client = rmcp::service::serve_client((), transport)
+    let client = rmcp::service::serve_client(handler, transport)
         .await
         .with_context(|| format!("Failed to initialize MCP client for: {}", url))?;
 
@@ -21,30 +28,606 @@ pub async fn connect_localhost(
 }
 
 /// Spawn a downstream MCP server via stdio (child process) transport.
+///
+/// With a `sandbox`, the child runs with the configured identity, environment,
+/// limits and namespaces instead of inheriting neurond's.
 pub async fn connect_stdio(
     command: &str,
     args: &[String],
     env: &HashMap<String, String>,
-) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    sandbox: Option<&StdioSandbox>,
+    handler: DownstreamHandler,
+) -> anyhow::Result<DownstreamClient> {
     let mut cmd = tokio::process::Command::new(command);
+    if let Some(sandbox) = sandbox {
+        sandbox::apply(&mut cmd, sandbox)
+            .with_context(|| format!("Failed to sandbox stdio downstream: {}", command))?;
+    }
     cmd.args(args).envs(env);
 
-    let transport = rmcp::transport::TokioChildProcess::new(cmd)?;
-    let client = rmcp::service::serve_client((), transport)
+    let (transport, stderr) = rmcp::transport::TokioChildProcess::builder(cmd)
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(stderr) = stderr {
+        tokio::spawn(capture_stderr(
+            handler.namespace().to_string(),
+            handler.stderr().clone(),
+            stderr,
+        ));
+    }
+    let client = rmcp::service::serve_client(handler, transport)
         .await
         .with_context(|| format!("MCP client init failed for stdio: {}", command))?;
 
     Ok(client)
 }
 
+/// Longest stderr line kept; the rest of a longer line is dropped.
+const STDERR_LINE_MAX: usize = 4096;
+
+/// Log each stderr line of a stdio child and keep it in the namespace's tail buffer.
+///
+/// Reads until EOF whatever the child writes, so the pipe never closes under it:
+/// invalid UTF-8 is replaced and overlong lines are truncated.
+async fn capture_stderr(
+    namespace: String,
+    tail: StderrTail,
+    stderr: impl tokio::io::AsyncRead + Unpin,
+) {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let mut reader = tokio::io::BufReader::new(stderr);
+    let mut buf = Vec::new();
+    // Inside a line that was already truncated
+    let mut truncated = false;
+    loop {
+        buf.clear();
+        match (&mut reader).take(STDERR_LINE_MAX as u64).read_until(b'\n', &mut buf).await {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(namespace = %namespace, error = %e, "Failed to read downstream stderr");
+                return;
+            }
+        }
+        let end_of_line = buf.last() == Some(&b'\n');
+        if !truncated {
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim_end_matches(['\n', '\r']).to_string();
+            tracing::info!(namespace = %namespace, stderr = %line, "Downstream stderr");
+            tail.push(line);
+        }
+        truncated = !end_of_line;
+    }
+}
+
+/// Connect to a downstream MCP server via streamable HTTP over a Unix domain socket.
+///
+/// With `expected_uid` set, every connection to the socket is checked via
+/// SO_PEERCRED and dropped if the process serving it runs as anyone else, so a
+/// socket replaced after startup is caught on the next connection.
+pub async fn connect_unix(
+    socket: &str,
+    path: &str,
+    expected_uid: Option<u32>,
+    handler: DownstreamHandler,
+) -> anyhow::Result<DownstreamClient> {
+    let mut relay = None;
+    let target = match expected_uid {
+        Some(expected) => {
+            // Fail early with a clear error; the relay enforces the check
+            connect_checked(socket, expected).await?;
+            let (path, guard) = uid_checked_relay(socket, expected)?;
+            relay = Some(guard);
+            path
+        }
+        None => PathBuf::from(socket),
+    };
+
+    let http = reqwest::Client::builder()
+        .unix_socket(target)
+        .build()
+        .context("Failed to build Unix socket HTTP client")?;
+    let mut http = HttpClient::new(http);
+    if let Some(guard) = relay {
+        http = http.holding(guard);
+    }
+    // The host is ignored on a Unix socket but still sent as the Host header
+    let uri = format!("http://localhost{}", path);
+    let transport = StreamableHttpClientTransport::with_client(http, StreamableHttpClientTransportConfig::with_uri(uri));
+
+    let client = rmcp::service::serve_client(handler, transport)
+        .await
+        .with_context(|| format!("Failed to initialize MCP client for socket: {}", socket))?;
+
+    Ok(client)
+}
+
+/// Connect to a Unix socket, refusing it unless the process serving it runs as `expected` uid.
+async fn connect_checked(socket: &str, expected: u32) -> anyhow::Result<tokio::net::UnixStream> {
+    let stream = tokio::net::UnixStream::connect(socket)
+        .await
+        .with_context(|| format!("Failed to connect to socket: {}", socket))?;
+    let uid = stream
+        .peer_cred()
+        .with_context(|| format!("Failed to read SO_PEERCRED for socket: {}", socket))?
+        .uid();
+    if uid != expected {
+        anyhow::bail!(
+            "Socket {} is served by a process running as uid {}, expected uid {} — refusing to connect",
+            socket,
+            uid,
+            expected
+        );
+    }
+    Ok(stream)
+}
+
+/// Serve a private socket that relays each connection to `socket` over a
+/// connection checked by [`connect_checked`].
+///
+/// reqwest only takes a socket path and connects by itself, so the check has
+/// to sit between it and the downstream. Returns the private socket's path,
+/// in a directory only neurond's uid can enter; the relay stops when the
+/// guard is dropped.
+fn uid_checked_relay(socket: &str, expected: u32) -> anyhow::Result<(PathBuf, DropGuard)> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let dir = std::env::temp_dir().join(format!("neurond-relay-{}", uuid::Uuid::new_v4()));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("Failed to create relay directory: {}", dir.display()))?;
+    let relay = dir.join("relay.sock");
+    let listener = tokio::net::UnixListener::bind(&relay)
+        .with_context(|| format!("Failed to bind relay socket: {}", relay.display()))?;
+
+    let ct = CancellationToken::new();
+    let stopped = ct.clone();
+    let socket = socket.to_string();
+    tokio::spawn(async move {
+        loop {
+            let mut client = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((client, _)) => client,
+                    Err(e) => {
+                        tracing::warn!(socket = %socket, error = %e, "Unix socket relay failed");
+                        break;
+                    }
+                },
+                _ = stopped.cancelled() => break,
+            };
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                match connect_checked(&socket, expected).await {
+                    Ok(mut server) => {
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                    }
+                    Err(e) => tracing::warn!(socket = %socket, error = %format!("{:#}", e), "Dropping Unix socket connection"),
+                }
+            });
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    });
+    Ok((relay, ct.drop_guard()))
+}
+
+/// Connect to a downstream MCP server on another host via streamable HTTP(S).
+///
+/// Applies the static headers, bearer token or OAuth credentials, CA bundle and
+/// client certificate from `auth`.
+pub async fn connect_remote(
+    url: &str,
+    auth: &RemoteAuth,
+    handler: DownstreamHandler,
+) -> anyhow::Result<DownstreamClient> {
+    if !url.starts_with("https://") {
+        tracing::warn!(url = %url, "Remote downstream is not using HTTPS — credentials are sent in cleartext");
+    }
+
+    let mut config = StreamableHttpClientTransportConfig::with_uri(url);
+    if let Some(path) = &auth.bearer_token_file {
+        if auth.oauth.is_some() {
+            anyhow::bail!("bearer_token_file and oauth are mutually exclusive");
+        }
+        let token = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bearer token file: {}", path))?;
+        config = config.auth_header(token.trim());
+    }
+    let http = remote_http_client(auth)?;
+    let mut client = HttpClient::new(http.clone());
+    if let Some(credentials) = &auth.oauth {
+        client = client.with_oauth(OAuthClient::new(http, url, credentials.clone()));
+    }
+    let transport = StreamableHttpClientTransport::with_client(client, config);
+
+    let client = rmcp::service::serve_client(handler, transport)
+        .await
+        .with_context(|| format!("Failed to initialize MCP client for: {}", url))?;
+
+    Ok(client)
+}
+
+/// Build the HTTP client for a remote downstream from its headers and TLS settings.
+fn remote_http_client(auth: &RemoteAuth) -> anyhow::Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in &auth.headers {
+        let name = reqwest::header::HeaderName::try_from(name.as_str())
+            .with_context(|| format!("Invalid header name: {}", name))?;
+        let mut value = reqwest::header::HeaderValue::try_from(value.as_str())
+            .with_context(|| format!("Invalid value for header: {}", name))?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+
+    if let Some(path) = &auth.ca_bundle {
+        let pem = std::fs::read(path).with_context(|| format!("Failed to read CA bundle: {}", path))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid CA bundle: {}", path))?
+        {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&auth.client_cert, &auth.client_key) {
+        (Some(cert), Some(key)) => {
+            let mut pem = std::fs::read(cert)
+                .with_context(|| format!("Failed to read client certificate: {}", cert))?;
+            pem.push(b'\n');
+            pem.extend(
+                std::fs::read(key).with_context(|| format!("Failed to read client key: {}", key))?,
+            );
+            let identity = reqwest::Identity::from_pem(&pem)
+                .with_context(|| format!("Invalid client certificate or key: {}", cert))?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => anyhow::bail!("client_cert and client_key must be set together"),
+    }
+
+    builder.build().context("Failed to build remote HTTP client")
+}
+
 /// Connect to a downstream based on its transport configuration.
 pub async fn connect_downstream(
     transport: &DownstreamTransport,
-) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    handler: DownstreamHandler,
+) -> anyhow::Result<DownstreamClient> {
     match transport {
-        DownstreamTransport::Localhost { url } => connect_localhost(url).await,
-        DownstreamTransport::Stdio { command, args, env } => {
-            connect_stdio(command, args, env).await
+        DownstreamTransport::Localhost { url } => connect_localhost(url, handler).await,
+        DownstreamTransport::Stdio {
+            command,
+            args,
+            env,
+            sandbox,
+        } => connect_stdio(command, args, env, sandbox.as_ref(), handler).await,
+        DownstreamTransport::Unix {
+            socket,
+            path,
+            expected_uid,
+        } => connect_unix(socket, path, *expected_uid, handler).await,
+        DownstreamTransport::Remote { url, auth } => connect_remote(url, auth, handler).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::Arc;
+    use tokio::sync::{broadcast, RwLock};
+
+    struct IdleDownstream;
+    impl rmcp::ServerHandler for IdleDownstream {}
+
+    #[tokio::test]
+    async fn test_capture_stderr_survives_binary_and_long_lines() {
+        let mut stderr = b"\xff\xfe\x00binary\n".to_vec();
+        stderr.extend(std::iter::repeat_n(b'x', STDERR_LINE_MAX * 3));
+        stderr.extend(b"\nmodel not found\n");
+        let tail = StderrTail::default();
+        capture_stderr("heavy".into(), tail.clone(), stderr.as_slice()).await;
+
+        let lines = tail.lines();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("binary"), "{:?}", lines[0]);
+        assert_eq!(lines[1], "x".repeat(STDERR_LINE_MAX));
+        assert_eq!(lines[2], "model not found");
+    }
+
+    /// Router serving an idle MCP server over streamable HTTP at `/mcp`.
+    fn mcp_router() -> axum::Router {
+        use rmcp::transport::streamable_http_server::{
+            session::local::LocalSessionManager, StreamableHttpService,
+        };
+
+        let service = StreamableHttpService::new(
+            || Ok(IdleDownstream),
+            LocalSessionManager::default().into(),
+            Default::default(),
+        );
+        axum::Router::new().nest_service("/mcp", service)
+    }
+
+    /// Serve an idle MCP server on a fresh Unix socket.
+    async fn serve_unix_downstream() -> std::path::PathBuf {
+        let socket = std::env::temp_dir().join(format!("neurond-{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move { axum::serve(listener, mcp_router()).await });
+        socket
+    }
+
+    /// Rejects requests lacking the bearer token and tenant header used in the remote test.
+    async fn require_credentials(
+        request: axum::extract::Request,
+        next: axum::middleware::Next,
+    ) -> axum::response::Response {
+        let headers = request.headers();
+        let authorized = headers.get("authorization").is_some_and(|v| v == "Bearer s3cret")
+            && headers.get("x-tenant").is_some_and(|v| v == "ops");
+        if !authorized {
+            return axum::http::StatusCode::FORBIDDEN.into_response();
         }
+        next.run(request).await
+    }
+
+    fn handler() -> DownstreamHandler {
+        let (events, _) = broadcast::channel(1);
+        DownstreamHandler::new(
+            "local".to_string(),
+            Vec::new(),
+            Arc::new(RwLock::new(Vec::new())),
+            events,
+            StderrTail::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_connect_remote_sends_headers_and_bearer_token() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let app = mcp_router().layer(axum::middleware::from_fn(require_credentials));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let token_file = std::env::temp_dir().join(format!("neurond-{}.token", uuid::Uuid::new_v4()));
+        std::fs::write(&token_file, "s3cret\n").unwrap();
+        let mut auth = RemoteAuth {
+            headers: HashMap::from([("X-Tenant".to_string(), "ops".to_string())]),
+            bearer_token_file: Some(token_file.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        let client = connect_remote(&url, &auth, handler()).await.unwrap();
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        client.cancel().await.unwrap();
+
+        auth.bearer_token_file = None;
+        assert!(connect_remote(&url, &auth, handler()).await.is_err());
+
+        let _ = std::fs::remove_file(token_file);
+    }
+
+    /// Stub OAuth deployment: protected-resource metadata naming `resource_path`,
+    /// authorization-server metadata, a client-credentials token endpoint
+    /// advertised under `token_host`, and an MCP endpoint at `/mcp` that demands its tokens.
+    /// Returns the MCP URL and a counter of issued tokens.
+    async fn serve_oauth_downstream(
+        resource_path: &'static str,
+        token_host: &'static str,
+    ) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use axum::routing::{get, post};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let issued = Arc::new(AtomicUsize::new(0));
+
+        let resource_metadata = {
+            let base = base.clone();
+            move || async move {
+                axum::Json(serde_json::json!({
+                    "resource": format!("{base}{resource_path}"),
+                    "authorization_servers": [format!("{base}/auth")],
+                }))
+            }
+        };
+        let server_metadata = {
+            let base = base.clone();
+            let token_base = base.replace("127.0.0.1", token_host);
+            move || async move {
+                axum::Json(serde_json::json!({
+                    "issuer": format!("{base}/auth"),
+                    "token_endpoint": format!("{token_base}/auth/token"),
+                }))
+            }
+        };
+        let token = {
+            let issued = issued.clone();
+            move |headers: axum::http::HeaderMap,
+                  axum::Form(form): axum::Form<HashMap<String, String>>| async move {
+                // "neurond:s3cret" in HTTP Basic
+                let basic = headers.get("authorization").is_some_and(|v| v == "Basic bmV1cm9uZDpzM2NyZXQ=");
+                if !basic || form.get("grant_type").map(String::as_str) != Some("client_credentials") {
+                    return axum::http::StatusCode::UNAUTHORIZED.into_response();
+                }
+                issued.fetch_add(1, Ordering::SeqCst);
+                axum::Json(serde_json::json!({
+                    "access_token": "oauth-token",
+                    "token_type": "Bearer",
+                    "expires_in": 3600,
+                }))
+                .into_response()
+            }
+        };
+        let challenge = format!(
+            r#"Bearer resource_metadata="{base}/.well-known/oauth-protected-resource/mcp""#
+        );
+        let require_token = move |request: axum::extract::Request, next: axum::middleware::Next| {
+            let challenge = challenge.clone();
+            async move {
+                let authorized = request
+                    .headers()
+                    .get("authorization")
+                    .is_some_and(|v| v == "Bearer oauth-token");
+                if !authorized {
+                    return (
+                        axum::http::StatusCode::UNAUTHORIZED,
+                        [("www-authenticate", challenge)],
+                    )
+                        .into_response();
+                }
+                next.run(request).await
+            }
+        };
+
+        let app = mcp_router()
+            .layer(axum::middleware::from_fn(require_token))
+            .route("/.well-known/oauth-protected-resource/mcp", get(resource_metadata))
+            .route("/.well-known/oauth-authorization-server/auth", get(server_metadata))
+            .route("/auth/token", post(token));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("{base}/mcp"), issued)
+    }
+
+    fn oauth_auth(secret_file: &std::path::Path, token_endpoint: Option<String>) -> RemoteAuth {
+        RemoteAuth {
+            oauth: Some(crate::config::OAuthClientCredentials {
+                client_id: "neurond".to_string(),
+                client_secret_file: secret_file.to_str().unwrap().to_string(),
+                scope: None,
+                token_endpoint,
+                issuer: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_remote_obtains_and_caches_oauth_token() {
+        let (url, issued) = serve_oauth_downstream("/mcp", "127.0.0.1").await;
+        let secret_file = std::env::temp_dir().join(format!("neurond-{}.secret", uuid::Uuid::new_v4()));
+        std::fs::write(&secret_file, "s3cret\n").unwrap();
+        let auth = oauth_auth(&secret_file, None);
+
+        let client = connect_remote(&url, &auth, handler()).await.unwrap();
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        // One 401 → discovery → token; later requests reuse the cached token
+        assert_eq!(issued.load(std::sync::atomic::Ordering::SeqCst), 1);
+        client.cancel().await.unwrap();
+
+        let _ = std::fs::remove_file(secret_file);
+    }
+
+    #[tokio::test]
+    async fn test_connect_remote_checks_oauth_resource_metadata() {
+        // The metadata describes some other resource, so no secret is sent
+        let (url, issued) = serve_oauth_downstream("/other", "127.0.0.1").await;
+        let secret_file = std::env::temp_dir().join(format!("neurond-{}.secret", uuid::Uuid::new_v4()));
+        std::fs::write(&secret_file, "s3cret\n").unwrap();
+
+        assert!(connect_remote(&url, &oauth_auth(&secret_file, None), handler()).await.is_err());
+        assert_eq!(issued.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        // A configured token endpoint is used without discovery
+        let token_endpoint = url.replace("/mcp", "/auth/token");
+        let client = connect_remote(&url, &oauth_auth(&secret_file, Some(token_endpoint)), handler())
+            .await
+            .unwrap();
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        assert_eq!(issued.load(std::sync::atomic::Ordering::SeqCst), 1);
+        client.cancel().await.unwrap();
+
+        let _ = std::fs::remove_file(secret_file);
+    }
+
+    #[tokio::test]
+    async fn test_connect_remote_checks_oauth_token_endpoint_origin() {
+        // The discovered token endpoint is on another origin than its issuer
+        let (url, issued) = serve_oauth_downstream("/mcp", "localhost").await;
+        let secret_file = std::env::temp_dir().join(format!("neurond-{}.secret", uuid::Uuid::new_v4()));
+        std::fs::write(&secret_file, "s3cret\n").unwrap();
+
+        assert!(connect_remote(&url, &oauth_auth(&secret_file, None), handler()).await.is_err());
+        assert_eq!(issued.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        // Pinning the issuer trusts the token endpoint its metadata names
+        let mut auth = oauth_auth(&secret_file, None);
+        auth.oauth.as_mut().unwrap().issuer = Some(url.replace("/mcp", "/auth"));
+        let client = connect_remote(&url, &auth, handler()).await.unwrap();
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        assert_eq!(issued.load(std::sync::atomic::Ordering::SeqCst), 1);
+        client.cancel().await.unwrap();
+
+        let _ = std::fs::remove_file(secret_file);
+    }
+
+    #[tokio::test]
+    async fn test_connect_stdio_captures_stderr() {
+        let handler = handler();
+        let tail = handler.stderr().clone();
+        let args = vec!["-c".to_string(), "echo 'fatal: no token' >&2; exit 1".to_string()];
+
+        let result = connect_stdio("/bin/sh", &args, &HashMap::new(), None, handler).await;
+        assert!(result.is_err());
+
+        let captured = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while tail.lines().is_empty() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(captured.is_ok(), "stderr should be captured");
+        assert_eq!(tail.lines(), vec!["fatal: no token"]);
+    }
+
+    #[test]
+    fn test_remote_client_requires_cert_and_key_together() {
+        let auth = RemoteAuth {
+            client_cert: Some("/etc/neurond/client.pem".to_string()),
+            ..Default::default()
+        };
+        let err = remote_http_client(&auth).unwrap_err();
+        assert!(err.to_string().contains("must be set together"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_unix_checks_peer_uid() {
+        let socket = serve_unix_downstream().await;
+        let path = socket.to_str().unwrap();
+        let own_uid = std::fs::metadata(&socket).unwrap().uid();
+
+        let client = connect_unix(path, "/mcp", Some(own_uid), handler())
+            .await
+            .unwrap();
+        assert!(crate::federation::lifecycle::ping(client.peer()).await);
+        client.cancel().await.unwrap();
+
+        let Err(err) = connect_unix(path, "/mcp", Some(own_uid.wrapping_add(1)), handler()).await
+        else {
+            panic!("socket owned by another uid should be refused");
+        };
+        assert!(err.to_string().contains("refusing to connect"));
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_uid_checked_relay_drops_connections_to_other_uids() {
+        use tokio::io::AsyncReadExt;
+
+        let socket = serve_unix_downstream().await;
+        let own_uid = std::fs::metadata(&socket).unwrap().uid();
+        let (relay, guard) = uid_checked_relay(socket.to_str().unwrap(), own_uid.wrapping_add(1)).unwrap();
+
+        let mut conn = tokio::net::UnixStream::connect(&relay).await.unwrap();
+        let mut buf = [0; 1];
+        let read = tokio::time::timeout(std::time::Duration::from_secs(5), conn.read(&mut buf)).await;
+        assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))), "relay should close the connection");
+
+        drop(guard);
+        let _ = std::fs::remove_file(socket);
     }
 }
@@ -0,0 +1,8 @@
+pub mod admin;
+pub mod cli;
+pub mod config;
+pub mod federation;
+pub mod upstream;
+pub mod security;
+pub mod registration;
+pub mod reload;
@@ -1,9 +1,3 @@
-pub mod config;
-pub mod federation;
-pub mod upstream;
-pub mod security;
-pub mod registration;
-
 use std::sync::Arc;
 use tracing_subscriber::EnvFilter;
 use tokio::net::TcpListener;
@@ -13,10 +7,13 @@ use rmcp::transport::streamable_http_server::{
     session::local::LocalSessionManager,
 };
 
-use crate::federation::manager::FederationManager;
-use crate::upstream::server::ProxyEngine;
-use crate::security::policy::Policy;
-use crate::security::audit::AuditLogger;
+use neurond::{admin, cli, config, registration, reload};
+use neurond::federation::manager::FederationManager;
+use neurond::upstream::server::ProxyEngine;
+use neurond::security::policy::{Policy, PolicyStore};
+use neurond::security::approval::ApprovalQueue;
+use neurond::security::audit::AuditLogger;
+use neurond::security::identity::IdentityResolver;
 
 /// Default paths for configuration and logging.
 const DEFAULT_POLICY_PATH: &str = "/etc/neurond/policy.toml";
@@ -28,6 +25,11 @@ const DEV_AUDIT_LOG: &str = "audit.log";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "policy") {
+        std::process::exit(cli::run_policy(&args[1..]));
+    }
+
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("neurond=info"));
 
@@ -49,12 +51,16 @@ async fn main() -> anyhow::Result<()> {
         DEV_POLICY_PATH
     };
 
-    let policy = Policy::load_from_file(policy_path).unwrap_or_else(|err| {
-        tracing::warn!("Failed to load {} ({}). Defaulting to Deny-All.", policy_path, err);
-        Policy::default()
-    });
-    tracing::info!("Loaded policy from {}", policy_path);
-    let policy = Arc::new(policy);
+    let policy = match Policy::load_with_hash(policy_path) {
+        Ok((policy, hash)) => {
+            tracing::info!(hash = %hash, "Loaded policy from {}", policy_path);
+            PolicyStore::new(policy, Some(hash))
+        }
+        Err(err) => {
+            tracing::warn!("Failed to load {} ({}). Defaulting to Deny-All.", policy_path, err);
+            PolicyStore::from(Policy::default())
+        }
+    };
 
     // Set up audit log
     let audit_path = if std::path::Path::new(DEFAULT_AUDIT_LOG)
@@ -68,10 +74,23 @@ async fn main() -> anyhow::Result<()> {
     let audit_logger = Arc::new(AuditLogger::new(audit_path));
     tracing::info!("Audit log: {}", audit_path);
 
+    // Secrets used to identify upstream callers for caller-scoped policy rules
+    let identity = Arc::new(IdentityResolver::load(&config.server.identity)?);
+    // Tool calls held by require_approval rules, decided via the admin API
+    let approvals = Arc::new(ApprovalQueue::new(&config.server.approval));
+
     // Initialize federation manager and connect to downstreams
     let federation = Arc::new(FederationManager::new());
     federation.init_from_config(&config.federation).await?;
 
+    // Reload the policy on SIGHUP or file change; a broken file keeps the old one
+    reload::spawn_policy_reloader(
+        policy_path.to_string(),
+        policy.clone(),
+        audit_logger.clone(),
+        federation.clone(),
+    )?;
+
     // Log connected downstreams
     let status = federation.status_summary().await;
     for (ns, state) in &status {
@@ -81,6 +100,9 @@ async fn main() -> anyhow::Result<()> {
     let tools = federation.list_all_tools().await;
     tracing::info!("Total tools aggregated: {}", tools.len());
 
+    // Apply downstream changes from neurond.toml without restarting
+    reload::spawn_config_reloader(config::config_path()?.to_string(), federation.clone())?;
+
     // Start registration/heartbeat if cortexd configured
     let _heartbeat_shutdown = if let Some(reg) = &config.registration {
         // Register with cortexd
@@ -116,15 +138,28 @@ async fn main() -> anyhow::Result<()> {
     let fed = federation.clone();
     let pol = policy.clone();
     let aud = audit_logger.clone();
+    let apr = approvals.clone();
     let mcp_service = StreamableHttpService::new(
         move || {
-            let engine = ProxyEngine::new(fed.clone(), pol.clone(), aud.clone());
+            let engine = ProxyEngine::new(fed.clone(), pol.clone(), aud.clone())
+                .with_identity(identity.clone())
+                .with_approvals(apr.clone());
             Ok(engine)
         },
         session_manager.into(),
         Default::default(),
     );
 
+    // Operator API on its own listener, behind the admin token
+    let admin_state = admin::AdminState {
+        policy_path: policy_path.to_string(),
+        policy: policy.clone(),
+        audit: audit_logger.clone(),
+        approvals,
+        federation: federation.clone(),
+    };
+    admin::spawn(&config.server.admin, admin_state).await?;
+
     let app = Router::new().nest_service("/api/v1/mcp", mcp_service);
     let listener = TcpListener::bind(&bind_addr).await?;
 
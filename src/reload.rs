@@ -0,0 +1,239 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::config::Config;
+use crate::federation::events::FederationEvent;
+use crate::federation::manager::FederationManager;
+use crate::security::audit::AuditLogger;
+use crate::security::policy::{Policy, PolicyStore};
+
+/// How often watched files' modification times are checked.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Spawn a background task that re-applies `[federation]` from the config file
+/// on SIGHUP or whenever the file changes.
+///
+/// Upstream sessions stay connected throughout. Changes to `[server]` and
+/// `[registration]` still need a restart.
+pub fn spawn_config_reloader(
+    path: String,
+    federation: Arc<FederationManager>,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    spawn_watcher(path, move |path| {
+        let federation = federation.clone();
+        async move { reload(&path, &federation).await }
+    })
+}
+
+/// Spawn a background task that reloads the policy on SIGHUP or whenever the
+/// policy file changes.
+pub fn spawn_policy_reloader(
+    path: String,
+    policy: PolicyStore,
+    audit: Arc<AuditLogger>,
+    federation: Arc<FederationManager>,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    spawn_watcher(path, move |path| {
+        let (policy, audit, federation) = (policy.clone(), audit.clone(), federation.clone());
+        async move {
+            // Failures are logged and audited by reload_policy
+            let _ = reload_policy(&path, &policy, &audit, &federation).await;
+        }
+    })
+}
+
+/// Run `on_change` for `path` on every SIGHUP and whenever its mtime changes.
+fn spawn_watcher<F, Fut>(path: String, on_change: F) -> anyhow::Result<tokio::task::JoinHandle<()>>
+where
+    F: Fn(String) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    Ok(tokio::spawn(async move {
+        let mut modified = modified_time(&path);
+        loop {
+            tokio::select! {
+                _ = hangup.recv() => {
+                    tracing::info!("SIGHUP received — reloading {}", path);
+                }
+                _ = tokio::time::sleep(WATCH_INTERVAL) => {
+                    if modified_time(&path) == modified {
+                        continue;
+                    }
+                    tracing::info!("{} changed — reloading", path);
+                }
+            }
+            modified = modified_time(&path);
+            on_change(path.clone()).await;
+        }
+    }))
+}
+
+/// Content hashes of the policy before and after a reload.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PolicyReload {
+    pub old_hash: Option<String>,
+    pub new_hash: String,
+}
+
+/// Re-read the policy file and swap it in.
+///
+/// A file that cannot be read or parsed leaves the current policy in effect.
+/// Both outcomes are written to the audit log with the content hashes. When the
+/// content changed, upstream sessions are told to re-list their (filtered) tools.
+pub async fn reload_policy(
+    path: &str,
+    policy: &PolicyStore,
+    audit: &AuditLogger,
+    federation: &FederationManager,
+) -> Result<PolicyReload, String> {
+    let (outcome, params) = match Policy::load_with_hash(path) {
+        Ok((new_policy, new_hash)) => {
+            let old_hash = policy.swap(new_policy, new_hash.clone());
+            tracing::info!(old_hash = ?old_hash, new_hash = %new_hash, "Policy reloaded from {}", path);
+            if old_hash.as_ref() != Some(&new_hash) {
+                federation.notify(FederationEvent::ToolListChanged);
+            }
+            let params = serde_json::json!({ "old_hash": old_hash, "new_hash": new_hash });
+            (Ok(PolicyReload { old_hash, new_hash }), params)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Policy reload failed — keeping current policy");
+            let params = serde_json::json!({ "old_hash": policy.hash(), "error": e });
+            (Err(e), params)
+        }
+    };
+
+    let (decision, result) = match &outcome {
+        Ok(_) => ("applied", "success"),
+        Err(_) => ("rejected", "error"),
+    };
+    if let Err(e) = audit
+        .log_request("policy/reload", path, &params, decision, result, 0)
+        .await
+    {
+        tracing::error!(error = %e, "Audit logging failed");
+    }
+    outcome
+}
+
+/// Re-read the config and apply its downstreams. An invalid file is logged
+/// and ignored, keeping the running downstreams as they are.
+async fn reload(path: &str, federation: &FederationManager) {
+    let config = match Config::load_from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!(error = %e, "Config reload failed — keeping current downstreams");
+            return;
+        }
+    };
+
+    let summary = federation.reload(&config.federation).await;
+    if summary.is_empty() {
+        tracing::info!("Config reloaded — downstreams unchanged");
+    } else {
+        tracing::info!(
+            added = ?summary.added,
+            removed = ?summary.removed,
+            changed = ?summary.changed,
+            "Config reloaded"
+        );
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reload_applies_valid_config_and_ignores_invalid() {
+        let dir = std::env::temp_dir().join(format!("neurond-reload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("neurond.toml");
+        let path = path.to_str().unwrap();
+
+        // A lazy downstream with a catalog registers without spawning anything
+        crate::federation::catalog::save(&dir, "heavy", &[]).unwrap();
+        std::fs::write(
+            path,
+            format!(
+                r#"
+                [server]
+
+                [federation]
+                catalog_dir = "{}"
+
+                [[federation.servers]]
+                namespace = "heavy"
+                transport = "stdio"
+                command = "/bin/false"
+                start = "lazy"
+                "#,
+                dir.display()
+            ),
+        )
+        .unwrap();
+
+        let federation = FederationManager::new();
+        reload(path, &federation).await;
+        assert_eq!(federation.namespaces().await, vec!["heavy"]);
+
+        std::fs::write(path, "[federation").unwrap();
+        reload(path, &federation).await;
+        assert_eq!(federation.namespaces().await, vec!["heavy"]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_reload_policy_swaps_and_audits() {
+        let dir = std::env::temp_dir().join(format!("neurond-reload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy_path = dir.join("policy.toml");
+        let policy_path = policy_path.to_str().unwrap();
+        let audit_path = dir.join("audit.log");
+        let audit = AuditLogger::new(audit_path.to_str().unwrap());
+        let federation = FederationManager::new();
+        let mut notifications = federation.subscribe();
+
+        std::fs::write(policy_path, r#"default_action = "allow""#).unwrap();
+        let (initial, initial_hash) = Policy::load_with_hash(policy_path).unwrap();
+        let store = PolicyStore::new(initial, Some(initial_hash.clone()));
+
+        let tightened = r#"default_action = "deny""#;
+        std::fs::write(policy_path, tightened).unwrap();
+        let reload = reload_policy(policy_path, &store, &audit, &federation).await.unwrap();
+        assert_eq!(reload.old_hash.as_deref(), Some(initial_hash.as_str()));
+        assert_eq!(reload.new_hash, crate::security::policy::content_hash(tightened));
+        assert!(!store.load().is_allowed("linux.system.cpu", &serde_json::json!({}), &Default::default()));
+        assert!(matches!(notifications.try_recv(), Ok(FederationEvent::ToolListChanged)));
+
+        // Reloading the same content changes nothing upstream
+        reload_policy(policy_path, &store, &audit, &federation).await.unwrap();
+        assert!(notifications.try_recv().is_err());
+
+        // A broken file is rejected and the tightened policy stays in effect
+        std::fs::write(policy_path, "default_action = ").unwrap();
+        assert!(reload_policy(policy_path, &store, &audit, &federation).await.is_err());
+        assert!(!store.load().is_allowed("linux.system.cpu", &serde_json::json!({}), &Default::default()));
+
+        let log = std::fs::read_to_string(audit_path).unwrap();
+        let events: Vec<serde_json::Value> =
+            log.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["method"], "policy/reload");
+        assert_eq!(events[0]["decision"], "applied");
+        assert_eq!(events[0]["params"]["old_hash"], initial_hash);
+        assert_eq!(events[0]["params"]["new_hash"], reload.new_hash);
+        assert_eq!(events[2]["decision"], "rejected");
+        assert_eq!(events[2]["params"]["old_hash"], reload.new_hash);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rmcp::model::{CreateElicitationRequestParams, ElicitationAction, ElicitationSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use crate::config::ApprovalConfig;
+use crate::federation::handler::UpstreamContext;
+use crate::security::audit::AuditLogger;
+use crate::security::identity::CallerIdentity;
+
+/// A tool call held by a `require_approval` rule, as shown to approvers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingCall {
+    pub id: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub caller: CallerIdentity,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A verdict on a pending call. Also the body the webhook answers with.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    /// Who decided: "admin" (or the name the operator gave), "webhook" or "elicitation"
+    #[serde(default)]
+    pub by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// How the wait for a decision ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalOutcome {
+    Decided(ApprovalDecision),
+    /// No decision within the TTL
+    Expired,
+    /// The upstream caller gave up first
+    Cancelled,
+}
+
+/// Pending tool calls awaiting a human decision.
+///
+/// A call is decided by whichever comes first: the admin API, the webhook, or
+/// the upstream client's answer to an elicitation. Shared by all upstream sessions.
+pub struct ApprovalQueue {
+    pending: Mutex<HashMap<String, Pending>>,
+    ttl: Duration,
+    webhook_url: Option<String>,
+    elicit: bool,
+    http: reqwest::Client,
+}
+
+struct Pending {
+    call: PendingCall,
+    decide: oneshot::Sender<ApprovalDecision>,
+}
+
+impl Default for ApprovalQueue {
+    fn default() -> Self {
+        Self::new(&ApprovalConfig::default())
+    }
+}
+
+impl ApprovalQueue {
+    pub fn new(config: &ApprovalConfig) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            ttl: Duration::from_secs(config.ttl_secs),
+            webhook_url: config.webhook_url.clone(),
+            elicit: config.elicit,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Calls currently waiting, oldest first.
+    pub fn pending(&self) -> Vec<PendingCall> {
+        let mut calls: Vec<PendingCall> = match self.pending.lock() {
+            Ok(pending) => pending.values().map(|p| p.call.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
+        calls.sort_by_key(|call| call.requested_at);
+        calls
+    }
+
+    /// Decide pending call `id`. Returns false if no such call is waiting.
+    pub fn resolve(&self, id: &str, decision: ApprovalDecision) -> bool {
+        let entry = self.pending.lock().ok().and_then(|mut pending| pending.remove(id));
+        match entry {
+            Some(entry) => entry.decide.send(decision).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Hold a tool call until it is decided, expires or the caller cancels it.
+    ///
+    /// Every step is written to `audit` (which should carry the caller).
+    pub async fn request(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        caller: &CallerIdentity,
+        upstream: Option<&UpstreamContext>,
+        audit: &AuditLogger,
+    ) -> ApprovalOutcome {
+        let requested_at = Utc::now();
+        let call = PendingCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            caller: caller.clone(),
+            requested_at,
+            expires_at: requested_at + self.ttl,
+        };
+        let (decide, decided) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(call.id.clone(), Pending { call: call.clone(), decide });
+        }
+
+        tracing::info!(tool = %tool, id = %call.id, "Tool call awaiting approval");
+        let params = serde_json::json!({
+            "id": call.id,
+            "arguments": arguments,
+            "expires_at": call.expires_at,
+        });
+        log(audit, "approval/request", tool, &params, "pending", "queued").await;
+
+        let upstream_cancelled = async {
+            match upstream {
+                Some(upstream) => upstream.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let outcome = tokio::select! {
+            Ok(decision) = decided => ApprovalOutcome::Decided(decision),
+            Some(decision) = self.ask_webhook(&call) => ApprovalOutcome::Decided(decision),
+            Some(decision) = self.ask_upstream(&call, upstream) => ApprovalOutcome::Decided(decision),
+            _ = tokio::time::sleep(self.ttl) => ApprovalOutcome::Expired,
+            _ = upstream_cancelled => ApprovalOutcome::Cancelled,
+        };
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&call.id);
+        }
+
+        let (decision, result, params) = match &outcome {
+            ApprovalOutcome::Decided(d) => (
+                if d.approved { "approved" } else { "rejected" },
+                "resolved",
+                serde_json::json!({ "id": call.id, "by": d.by, "reason": d.reason }),
+            ),
+            ApprovalOutcome::Expired => ("expired", "timeout", serde_json::json!({ "id": call.id })),
+            ApprovalOutcome::Cancelled => ("cancelled", "cancelled", serde_json::json!({ "id": call.id })),
+        };
+        tracing::info!(tool = %tool, id = %call.id, decision = %decision, "Approval finished");
+        log(audit, "approval/decision", tool, &params, decision, result).await;
+        outcome
+    }
+
+    /// POST the call to the webhook. A 200 with an [`ApprovalDecision`] body decides;
+    /// anything else leaves the call to the other approvers.
+    async fn ask_webhook(&self, call: &PendingCall) -> Option<ApprovalDecision> {
+        let url = self.webhook_url.as_ref()?;
+        let response = match self.http.post(url).json(call).send().await {
+            Ok(response) if response.status() == reqwest::StatusCode::OK => response,
+            Ok(response) => {
+                tracing::debug!(id = %call.id, status = %response.status(), "Approval webhook deferred");
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!(id = %call.id, error = %e, "Approval webhook failed");
+                return None;
+            }
+        };
+        match response.json::<ApprovalDecision>().await {
+            Ok(decision) => Some(ApprovalDecision {
+                by: "webhook".to_string(),
+                ..decision
+            }),
+            Err(e) => {
+                tracing::warn!(id = %call.id, error = %e, "Approval webhook answered with an invalid decision");
+                None
+            }
+        }
+    }
+
+    /// Ask the upstream client to confirm the call, if it supports elicitation.
+    async fn ask_upstream(&self, call: &PendingCall, upstream: Option<&UpstreamContext>) -> Option<ApprovalDecision> {
+        let upstream = upstream.filter(|_| self.elicit)?;
+        let supported = upstream
+            .peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.elicitation.is_some());
+        if !supported {
+            return None;
+        }
+
+        let request = CreateElicitationRequestParams::FormElicitationParams {
+            meta: None,
+            message: format!(
+                "Policy requires approval to call {} with arguments {}",
+                call.tool, call.arguments
+            ),
+            requested_schema: ElicitationSchema::builder()
+                .required_bool_property("approve", |s| s.description("Run this tool call"))
+                .build_unchecked(),
+        };
+        let result = match upstream.peer.create_elicitation(request).await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(id = %call.id, error = %e, "Approval elicitation failed");
+                return None;
+            }
+        };
+        let approved = result.action == ElicitationAction::Accept
+            && result
+                .content
+                .as_ref()
+                .and_then(|content| content.get("approve"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+        Some(ApprovalDecision {
+            approved,
+            by: "elicitation".to_string(),
+            reason: None,
+        })
+    }
+}
+
+async fn log(audit: &AuditLogger, method: &str, tool: &str, params: &Value, decision: &str, result: &str) {
+    if let Err(e) = audit.log_request(method, tool, params, decision, result, 0).await {
+        tracing::error!(error = %e, "Audit logging failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn new_queue(ttl_secs: u64, webhook_url: Option<String>) -> Arc<ApprovalQueue> {
+        Arc::new(ApprovalQueue::new(&ApprovalConfig {
+            ttl_secs,
+            webhook_url,
+            elicit: false,
+        }))
+    }
+
+    fn audit_log() -> (AuditLogger, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("neurond-approval-{}.log", uuid::Uuid::new_v4()));
+        (AuditLogger::new(path.to_str().unwrap()), path)
+    }
+
+    fn audit_events(path: &std::path::Path) -> Vec<Value> {
+        let log = std::fs::read_to_string(path).unwrap();
+        log.lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+    }
+
+    /// Wait for the call to show up in the queue.
+    async fn first_pending(queue: &ApprovalQueue) -> PendingCall {
+        loop {
+            if let Some(call) = queue.pending().into_iter().next() {
+                return call;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_decision_resolves_call() {
+        let queue = new_queue(60, None);
+        let (audit, path) = audit_log();
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let args = serde_json::json!({"unit": "nginx"});
+                queue
+                    .request("linux.service.restart", &args, &CallerIdentity::default(), None, &audit)
+                    .await
+            })
+        };
+        let call = first_pending(&queue).await;
+        assert_eq!(call.tool, "linux.service.restart");
+
+        let decision = ApprovalDecision {
+            approved: true,
+            by: "alice".into(),
+            reason: Some("planned maintenance".into()),
+        };
+        assert!(queue.resolve(&call.id, decision.clone()));
+        assert_eq!(waiter.await.unwrap(), ApprovalOutcome::Decided(decision));
+        assert!(queue.pending().is_empty());
+        assert!(!queue.resolve(&call.id, ApprovalDecision { approved: false, by: "bob".into(), reason: None }));
+
+        let events = audit_events(&path);
+        assert_eq!(events[0]["method"], "approval/request");
+        assert_eq!(events[0]["decision"], "pending");
+        assert_eq!(events[1]["method"], "approval/decision");
+        assert_eq!(events[1]["decision"], "approved");
+        assert_eq!(events[1]["params"]["by"], "alice");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_undecided_call_expires() {
+        let queue = new_queue(0, None);
+        let (audit, path) = audit_log();
+        let outcome = queue
+            .request("linux.service.restart", &serde_json::json!({}), &CallerIdentity::default(), None, &audit)
+            .await;
+        assert_eq!(outcome, ApprovalOutcome::Expired);
+        assert!(queue.pending().is_empty());
+        assert_eq!(audit_events(&path)[1]["decision"], "expired");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_decides_or_defers() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new()
+            .route(
+                "/reject",
+                post(|Json(call): Json<Value>| async move {
+                    Json(serde_json::json!({ "approved": false, "reason": format!("no {}", call["tool"]) }))
+                }),
+            )
+            .route("/defer", post(|| async { axum::http::StatusCode::ACCEPTED }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let (audit, path) = audit_log();
+
+        let queue = new_queue(60, Some(format!("{base}/reject")));
+        let outcome = queue
+            .request("linux.service.stop", &serde_json::json!({}), &CallerIdentity::default(), None, &audit)
+            .await;
+        assert_eq!(
+            outcome,
+            ApprovalOutcome::Decided(ApprovalDecision {
+                approved: false,
+                by: "webhook".into(),
+                reason: Some("no \"linux.service.stop\"".into()),
+            })
+        );
+
+        // A deferring webhook leaves the call to the admin API
+        let queue = new_queue(60, Some(format!("{base}/defer")));
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                queue
+                    .request("linux.service.stop", &serde_json::json!({}), &CallerIdentity::default(), None, &audit)
+                    .await
+            })
+        };
+        let call = first_pending(&queue).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+        let decision = ApprovalDecision { approved: true, by: "admin".into(), reason: None };
+        assert!(queue.resolve(&call.id, decision.clone()));
+        assert_eq!(waiter.await.unwrap(), ApprovalOutcome::Decided(decision));
+        let _ = std::fs::remove_file(path);
+    }
+}
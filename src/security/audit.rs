@@ -4,19 +4,33 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use chrono::Utc;
 
+use crate::security::identity::CallerIdentity;
+use crate::security::policy::{Effect, PolicyDecision};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AuditEvent {
     pub timestamp: String,
+    /// MCP method being audited (e.g. "tools/call", "resources/read", "prompts/get")
+    pub method: String,
+    /// Namespaced target of the call: tool name, resource URI or prompt name
     pub tool: String,
     pub params: Value,
     pub decision: String,
     pub result: String,
     pub duration_ms: u64,
+    /// Upstream caller the decision was made for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caller: Option<CallerIdentity>,
+    /// Policy decision behind `decision`, with the rules that matched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyDecision>,
 }
 
 #[derive(Clone)]
 pub struct AuditLogger {
     log_path: String,
+    caller: Option<CallerIdentity>,
+    policy: Option<PolicyDecision>,
 }
 
 impl Default for AuditLogger {
@@ -29,9 +43,28 @@ impl AuditLogger {
     pub fn new(path: &str) -> Self {
         Self {
             log_path: path.to_string(),
+            caller: None,
+            policy: None,
+        }
+    }
+
+    /// A logger for the same file that records `caller` on every event.
+    pub fn for_caller(&self, caller: &CallerIdentity) -> Self {
+        Self {
+            caller: Some(caller.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// A logger that also records the policy decision `policy` on every event.
+    pub fn with_policy(&self, policy: &PolicyDecision) -> Self {
+        Self {
+            policy: Some(policy.clone()),
+            ..self.clone()
         }
     }
 
+    /// Record a `tools/call` decision.
     pub async fn log(
         &self,
         tool: &str,
@@ -39,16 +72,61 @@ impl AuditLogger {
         decision: &str,
         result: &str,
         duration_ms: u64,
+    ) -> anyhow::Result<()> {
+        self.log_request("tools/call", tool, params, decision, result, duration_ms)
+            .await
+    }
+
+    /// Record a `policy/shadow` event if the candidate policy decided a request
+    /// differently from the enforced one.
+    pub async fn log_shadow(
+        &self,
+        method: &str,
+        target: &str,
+        params: &Value,
+        enforced: &PolicyDecision,
+        candidate: &PolicyDecision,
+    ) {
+        if enforced.effect == candidate.effect {
+            return;
+        }
+        let decision = match candidate.effect {
+            Effect::Allow => "would_allow",
+            Effect::Deny => "would_deny",
+            Effect::RequireApproval => "would_require_approval",
+        };
+        tracing::info!(method = %method, target = %target, decision = %decision, "Candidate policy disagrees");
+        let params = serde_json::json!({
+            "method": method,
+            "enforced": enforced,
+            "candidate": candidate,
+            "params": params,
+        });
+        let _ = self.log_request("policy/shadow", target, &params, decision, "shadow", 0).await;
+    }
+
+    /// Record a decision for any audited MCP method against a namespaced target.
+    pub async fn log_request(
+        &self,
+        method: &str,
+        target: &str,
+        params: &Value,
+        decision: &str,
+        result: &str,
+        duration_ms: u64,
     ) -> anyhow::Result<()> {
         let timestamp = Utc::now().to_rfc3339();
 
         let event = AuditEvent {
             timestamp,
-            tool: tool.to_string(),
+            method: method.to_string(),
+            tool: target.to_string(),
             params: params.clone(),
             decision: decision.to_string(),
             result: result.to_string(),
             duration_ms,
+            caller: self.caller.clone(),
+            policy: self.policy.clone(),
         };
 
         let json_line = serde_json::to_string(&event)?;
@@ -82,16 +160,43 @@ mod tests {
     fn test_audit_event_serialization() {
         let event = AuditEvent {
             timestamp: "2026-02-22T14:03:43Z".into(),
+            method: "tools/call".into(),
             tool: "system.cpu".into(),
             params: serde_json::json!({"test": true}),
             decision: "allowed".into(),
             result: "success".into(),
             duration_ms: 12,
+            caller: None,
+            policy: None,
         };
 
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("timestamp"));
+        assert!(json.contains("tools/call"));
         assert!(json.contains("system.cpu"));
         assert!(json.contains("12"));
+        assert!(!json.contains("caller"));
+    }
+
+    #[tokio::test]
+    async fn test_tagged_logger_records_caller_and_policy() {
+        let path = std::env::temp_dir().join(format!("neurond-audit-{}.log", uuid::Uuid::new_v4()));
+        let caller = CallerIdentity {
+            client_name: Some("cortexd".into()),
+            token_id: Some("ops-bot".into()),
+            ..Default::default()
+        };
+        let policy: crate::security::policy::Policy = toml::from_str(r#"default_action = "allow""#).unwrap();
+        let decision = policy.decide_tool("system.cpu", &serde_json::json!({}), &caller);
+        let audit = AuditLogger::new(path.to_str().unwrap())
+            .for_caller(&caller)
+            .with_policy(&decision);
+        audit.log("system.cpu", &serde_json::json!({}), "allowed", "success", 1).await.unwrap();
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        let event: Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(event["caller"], serde_json::json!({"client_name": "cortexd", "token_id": "ops-bot"}));
+        assert_eq!(event["policy"]["default_applied"], true);
+        let _ = std::fs::remove_file(path);
     }
 }
@@ -0,0 +1,243 @@
+use std::path::{Component, Path, PathBuf};
+
+use regex_automata::meta::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A test on one tool call argument, addressed by JSON pointer.
+///
+/// Every test that is set must hold. A string argument that is an absolute
+/// path is matched by `glob` and `regex` with `.` and `..` resolved.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArgumentCondition {
+    /// JSON pointer into the call arguments (e.g. "/unit", "/options/0/path")
+    pub pointer: String,
+    /// Argument equals this value exactly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    /// String argument matches this glob
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// String argument matches this regex in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Numeric argument is at least this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Numeric argument is at most this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// String argument is an absolute path inside this directory, after resolving `.` and `..`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+}
+
+/// The glob and regex of an [`ArgumentCondition`], parsed once by
+/// [`ArgumentCondition::patterns`]. An invalid pattern is left out.
+#[derive(Debug, Clone, Default)]
+pub struct ConditionPatterns {
+    glob: Option<glob::Pattern>,
+    regex: Option<Regex>,
+}
+
+impl ArgumentCondition {
+    /// Check the condition against a call's arguments object. A condition
+    /// that cannot be evaluated does not hold.
+    pub fn matches(&self, arguments: &Value) -> bool {
+        self.evaluate(arguments) == Some(true)
+    }
+
+    /// Whether the condition holds, or None if no test fails but some cannot be
+    /// evaluated: the argument is missing or of the wrong type, the path is
+    /// relative, or the pattern is invalid.
+    pub fn evaluate(&self, arguments: &Value) -> Option<bool> {
+        self.evaluate_with(arguments, &self.patterns())
+    }
+
+    /// Parse the condition's glob and regex for [`evaluate_with`](Self::evaluate_with).
+    pub fn patterns(&self) -> ConditionPatterns {
+        ConditionPatterns {
+            glob: self.glob.as_deref().and_then(|p| glob::Pattern::new(p).ok()),
+            regex: self.regex.as_deref().and_then(|p| Regex::new(&anchored(p)).ok()),
+        }
+    }
+
+    /// [`evaluate`](Self::evaluate) with the patterns parsed beforehand.
+    pub fn evaluate_with(&self, arguments: &Value, patterns: &ConditionPatterns) -> Option<bool> {
+        let value = arguments.pointer(&self.pointer)?;
+
+        let mut tests = Vec::new();
+        if let Some(expected) = &self.equals {
+            tests.push(Some(value == expected));
+        }
+        if self.glob.is_some() || self.regex.is_some() || self.path_prefix.is_some() {
+            let s = value.as_str();
+            let resolved = s.and_then(normalize);
+            let s = resolved.as_deref().and_then(Path::to_str).or(s);
+            if self.glob.is_some() {
+                tests.push(s.and_then(|s| Some(patterns.glob.as_ref()?.matches(s))));
+            }
+            if self.regex.is_some() {
+                tests.push(s.and_then(|s| Some(patterns.regex.as_ref()?.is_match(s))));
+            }
+            if let Some(prefix) = &self.path_prefix {
+                tests.push(s.and_then(|s| is_under(prefix, s)));
+            }
+        }
+        if self.min.is_some() || self.max.is_some() {
+            tests.push(
+                value
+                    .as_f64()
+                    .map(|n| !(self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max))),
+            );
+        }
+
+        if tests.contains(&Some(false)) {
+            Some(false)
+        } else if tests.contains(&None) {
+            None
+        } else {
+            Some(true)
+        }
+    }
+
+    /// Tests in the condition that can never hold as written, which `matches`
+    /// would otherwise treat as silently failing.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.pointer.is_empty() && !self.pointer.starts_with('/') {
+            problems.push(format!("pointer {:?} must be empty or start with '/'", self.pointer));
+        }
+        if let Some(pattern) = &self.glob {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                problems.push(format!("invalid glob {:?}: {}", pattern, e));
+            }
+        }
+        if let Some(pattern) = &self.regex {
+            if let Err(e) = Regex::new(&anchored(pattern)) {
+                problems.push(format!("invalid regex {:?}: {}", pattern, e));
+            }
+        }
+        if let Some(prefix) = &self.path_prefix {
+            if normalize(prefix).is_none() {
+                problems.push(format!("path_prefix {:?} is not absolute", prefix));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min > max {
+                problems.push(format!("min {} is greater than max {}", min, max));
+            }
+        }
+        problems
+    }
+}
+
+/// `pattern` made to match only the whole value.
+fn anchored(pattern: &str) -> String {
+    format!("^(?:{})$", pattern)
+}
+
+/// Whether `path` lies inside `prefix`, compared by whole components after
+/// lexically resolving `.` and `..` so "/var/log/../../etc" does not pass.
+/// None unless both are absolute.
+fn is_under(prefix: &str, path: &str) -> Option<bool> {
+    Some(normalize(path)?.starts_with(normalize(prefix)?))
+}
+
+/// Resolve `.` and `..` without touching the filesystem. Relative paths yield None.
+fn normalize(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(toml: &str) -> ArgumentCondition {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_string_tests() {
+        let args = serde_json::json!({"unit": "nginx.service", "opts": {"mode": "graceful"}});
+
+        assert!(condition("pointer = \"/unit\"\nglob = \"nginx*\"").matches(&args));
+        assert!(!condition("pointer = \"/unit\"\nglob = \"postgres*\"").matches(&args));
+        assert!(condition("pointer = \"/unit\"\nregex = 'nginx\\.(service|socket)'").matches(&args));
+        // Regexes must match the whole value
+        assert!(!condition("pointer = \"/unit\"\nregex = 'nginx'").matches(&args));
+        assert!(condition("pointer = \"/opts/mode\"\nequals = \"graceful\"").matches(&args));
+        // A missing argument never satisfies a condition
+        assert!(!condition("pointer = \"/force\"\nequals = false").matches(&args));
+    }
+
+    #[test]
+    fn test_numeric_range() {
+        let within = condition("pointer = \"/lines\"\nmin = 1\nmax = 500");
+        assert!(within.matches(&serde_json::json!({"lines": 100})));
+        assert!(within.matches(&serde_json::json!({"lines": 500})));
+        assert!(!within.matches(&serde_json::json!({"lines": 501})));
+        assert!(!within.matches(&serde_json::json!({"lines": "100"})));
+    }
+
+    #[test]
+    fn test_path_prefix_resolves_dot_dot() {
+        let logs = condition("pointer = \"/path\"\npath_prefix = \"/var/log\"");
+        assert!(logs.matches(&serde_json::json!({"path": "/var/log/syslog"})));
+        assert!(logs.matches(&serde_json::json!({"path": "/var/log/./nginx/access.log"})));
+        assert!(!logs.matches(&serde_json::json!({"path": "/var/log/../../etc/shadow"})));
+        assert!(!logs.matches(&serde_json::json!({"path": "/var/logs-archive/x"})));
+        assert!(!logs.matches(&serde_json::json!({"path": "var/log/syslog"})));
+    }
+
+    #[test]
+    fn test_glob_and_regex_see_resolved_paths() {
+        let etc = condition("pointer = \"/path\"\nglob = \"/etc/*\"");
+        assert!(etc.matches(&serde_json::json!({"path": "/tmp/../etc/shadow"})));
+        assert!(etc.matches(&serde_json::json!({"path": "/etc//./shadow"})));
+        assert!(!etc.matches(&serde_json::json!({"path": "/etc/../tmp/x"})));
+        let shadow = condition("pointer = \"/path\"\nregex = '/etc/shadow'");
+        assert!(shadow.matches(&serde_json::json!({"path": "/var/log/../../etc/shadow"})));
+        // Other strings are matched as they are
+        assert!(condition("pointer = \"/unit\"\nglob = \"a/../b\"").matches(&serde_json::json!({"unit": "a/../b"})));
+    }
+
+    #[test]
+    fn test_unevaluable_conditions() {
+        let nginx = condition("pointer = \"/unit\"\nglob = \"nginx*\"");
+        assert_eq!(nginx.evaluate(&serde_json::json!({})), None);
+        assert_eq!(nginx.evaluate(&serde_json::json!({"unit": 7})), None);
+        assert_eq!(condition("pointer = \"/unit\"\nglob = \"[nginx\"").evaluate(&serde_json::json!({"unit": "x"})), None);
+        // A test that fails outright decides the condition
+        let both = condition("pointer = \"/lines\"\nequals = 5\nglob = \"*\"");
+        assert_eq!(both.evaluate(&serde_json::json!({"lines": 6})), Some(false));
+        assert_eq!(both.evaluate(&serde_json::json!({"lines": 5})), None);
+    }
+
+    #[test]
+    fn test_problems() {
+        assert!(condition("pointer = \"/unit\"\nglob = \"nginx*\"\nregex = 'a|b'").problems().is_empty());
+        let broken = condition("pointer = \"unit\"\nglob = \"[nginx\"\nregex = '(a'\nmin = 5\nmax = 1\npath_prefix = \"var\"");
+        assert_eq!(broken.problems().len(), 5);
+    }
+
+    #[test]
+    fn test_unknown_test_is_rejected() {
+        assert!(toml::from_str::<ArgumentCondition>("pointer = \"/path\"\nprefix = \"/var/log\"").is_err());
+    }
+}
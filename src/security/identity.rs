@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use http::header::AUTHORIZATION;
+use http::HeaderName;
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::IdentityConfig;
+
+/// Who is making an upstream request, as far as neurond can tell.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CallerIdentity {
+    /// `clientInfo.name` sent in `initialize`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    /// ID of the configured static bearer token presented
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
+    /// Client certificate subject reported by the TLS-terminating proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert_subject: Option<String>,
+    /// Claims of a verified JWT bearer token
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub claims: Map<String, Value>,
+}
+
+/// Caller pattern of a policy rule. Every field that is set must match (globs).
+///
+/// A claim matches if it is a string matching the glob, or an array with such a string.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CallerMatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_subject: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub claims: HashMap<String, String>,
+}
+
+/// The globs of a [`CallerMatch`], parsed once by [`CallerMatch::patterns`].
+/// An invalid glob is left out.
+#[derive(Debug, Clone, Default)]
+pub struct CallerPatterns {
+    client_name: Option<glob::Pattern>,
+    token_id: Option<glob::Pattern>,
+    cert_subject: Option<glob::Pattern>,
+    claims: HashMap<String, glob::Pattern>,
+}
+
+impl CallerMatch {
+    pub fn matches(&self, caller: &CallerIdentity) -> bool {
+        self.matches_with(caller, &self.patterns())
+    }
+
+    /// Parse the globs for [`matches_with`](Self::matches_with).
+    pub fn patterns(&self) -> CallerPatterns {
+        let parse = |pattern: &Option<String>| pattern.as_deref().and_then(|p| glob::Pattern::new(p).ok());
+        CallerPatterns {
+            client_name: parse(&self.client_name),
+            token_id: parse(&self.token_id),
+            cert_subject: parse(&self.cert_subject),
+            claims: self
+                .claims
+                .iter()
+                .filter_map(|(name, pattern)| Some((name.clone(), glob::Pattern::new(pattern).ok()?)))
+                .collect(),
+        }
+    }
+
+    /// [`matches`](Self::matches) with the globs parsed beforehand.
+    pub fn matches_with(&self, caller: &CallerIdentity, patterns: &CallerPatterns) -> bool {
+        let field = |pattern: &Option<String>, glob: &Option<glob::Pattern>, value: &Option<String>| {
+            pattern.is_none() || glob.as_ref().zip(value.as_deref()).is_some_and(|(glob, v)| glob.matches(v))
+        };
+        field(&self.client_name, &patterns.client_name, &caller.client_name)
+            && field(&self.token_id, &patterns.token_id, &caller.token_id)
+            && field(&self.cert_subject, &patterns.cert_subject, &caller.cert_subject)
+            && self.claims.keys().all(|name| {
+                let Some(glob) = patterns.claims.get(name) else {
+                    return false;
+                };
+                match caller.claims.get(name) {
+                    Some(Value::String(s)) => glob.matches(s),
+                    Some(Value::Array(items)) => items.iter().any(|v| v.as_str().is_some_and(|s| glob.matches(s))),
+                    _ => false,
+                }
+            })
+    }
+}
+
+/// Derives a [`CallerIdentity`] from an upstream HTTP request.
+///
+/// Secrets are read once at startup. Bearer tokens that are neither a configured
+/// static token nor a valid JWT are ignored, leaving the caller without a token identity.
+#[derive(Default)]
+pub struct IdentityResolver {
+    /// (token ID, SHA-256 of the token)
+    tokens: Vec<(String, Vec<u8>)>,
+    jwt_key: Option<hmac::Key>,
+    jwt_issuer: Option<String>,
+    jwt_audience: Option<String>,
+    cert_subject_header: Option<HeaderName>,
+}
+
+impl IdentityResolver {
+    pub fn load(config: &IdentityConfig) -> anyhow::Result<Self> {
+        let mut tokens = Vec::new();
+        for token in &config.tokens {
+            let value = std::fs::read_to_string(&token.token_file)
+                .with_context(|| format!("Failed to read bearer token file: {}", token.token_file))?;
+            tokens.push((token.id.clone(), sha256(value.trim())));
+        }
+        let jwt_key = match &config.jwt_secret_file {
+            Some(path) => {
+                let secret = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read JWT secret file: {}", path))?;
+                Some(hmac::Key::new(hmac::HMAC_SHA256, secret.trim().as_bytes()))
+            }
+            None => None,
+        };
+        let cert_subject_header = config
+            .cert_subject_header
+            .as_deref()
+            .map(HeaderName::try_from)
+            .transpose()
+            .context("Invalid cert_subject_header")?;
+        Ok(Self {
+            tokens,
+            jwt_key,
+            jwt_issuer: config.jwt_issuer.clone(),
+            jwt_audience: config.jwt_audience.clone(),
+            cert_subject_header,
+        })
+    }
+
+    /// Identify the caller of a request. `parts` is absent for non-HTTP transports.
+    pub fn resolve(&self, parts: Option<&http::request::Parts>, client_name: Option<&str>) -> CallerIdentity {
+        let mut caller = CallerIdentity {
+            client_name: client_name.map(str::to_string),
+            ..Default::default()
+        };
+        let Some(parts) = parts else {
+            return caller;
+        };
+
+        caller.cert_subject = self
+            .cert_subject_header
+            .as_ref()
+            .and_then(|name| parts.headers.get(name))
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        let bearer = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim);
+        if let Some(token) = bearer {
+            let hash = sha256(token);
+            caller.token_id = self
+                .tokens
+                .iter()
+                .find(|(_, expected)| *expected == hash)
+                .map(|(id, _)| id.clone());
+            if caller.token_id.is_none() {
+                if let Some(key) = &self.jwt_key {
+                    caller.claims = verify_jwt(key, token)
+                        .filter(|claims| self.accepts_claims(claims))
+                        .unwrap_or_default();
+                }
+            }
+        }
+        caller
+    }
+}
+
+impl IdentityResolver {
+    /// Whether verified JWT claims name the configured issuer and audience.
+    fn accepts_claims(&self, claims: &Map<String, Value>) -> bool {
+        let issuer = self
+            .jwt_issuer
+            .as_deref()
+            .is_none_or(|issuer| claims.get("iss").and_then(Value::as_str) == Some(issuer));
+        let audience = self.jwt_audience.as_deref().is_none_or(|audience| match claims.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        });
+        issuer && audience
+    }
+}
+
+fn sha256(value: &str) -> Vec<u8> {
+    digest::digest(&digest::SHA256, value.as_bytes()).as_ref().to_vec()
+}
+
+/// Verify an HS256 JWT and return its claims, rejecting expired or not-yet-valid
+/// tokens and tokens whose `exp` or `nbf` is not a whole number of seconds.
+fn verify_jwt(key: &hmac::Key, token: &str) -> Option<Map<String, Value>> {
+    let (signed, signature) = token.rsplit_once('.')?;
+    let (header, payload) = signed.split_once('.')?;
+
+    let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+    if header.get("alg")?.as_str()? != "HS256" {
+        return None;
+    }
+    hmac::verify(key, signed.as_bytes(), &URL_SAFE_NO_PAD.decode(signature).ok()?).ok()?;
+
+    let claims: Map<String, Value> = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let claim_time = |name: &str| match claims.get(name) {
+        None => Some(None),
+        Some(value) => value.as_u64().map(Some),
+    };
+    let (exp, nbf) = (claim_time("exp")?, claim_time("nbf")?);
+    if exp.is_some_and(|exp| now >= exp) || nbf.is_some_and(|nbf| now < nbf) {
+        return None;
+    }
+    Some(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign_jwt(secret: &str, claims: Value) -> String {
+        let encode = |v: Value| URL_SAFE_NO_PAD.encode(serde_json::to_vec(&v).unwrap());
+        let signed = format!("{}.{}", encode(serde_json::json!({"alg": "HS256", "typ": "JWT"})), encode(claims));
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(hmac::sign(&key, signed.as_bytes()).as_ref());
+        format!("{}.{}", signed, signature)
+    }
+
+    fn request(headers: &[(&str, &str)]) -> http::request::Parts {
+        let mut builder = http::Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_resolve_tokens_jwt_and_cert_subject() {
+        let dir = std::env::temp_dir().join(format!("neurond-identity-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ops.token"), "s3cret\n").unwrap();
+        std::fs::write(dir.join("jwt.secret"), "jwt-key").unwrap();
+        let config: IdentityConfig = toml::from_str(&format!(
+            r#"
+            jwt_secret_file = "{dir}/jwt.secret"
+            cert_subject_header = "X-SSL-Client-S-DN"
+            tokens = [{{ id = "ops-bot", token_file = "{dir}/ops.token" }}]
+            "#,
+            dir = dir.display()
+        ))
+        .unwrap();
+        let resolver = IdentityResolver::load(&config).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let caller = resolver.resolve(
+            Some(&request(&[("authorization", "Bearer s3cret"), ("x-ssl-client-s-dn", "CN=ops-1")])),
+            Some("cortexd"),
+        );
+        assert_eq!(caller.client_name.as_deref(), Some("cortexd"));
+        assert_eq!(caller.token_id.as_deref(), Some("ops-bot"));
+        assert_eq!(caller.cert_subject.as_deref(), Some("CN=ops-1"));
+
+        let jwt = sign_jwt("jwt-key", serde_json::json!({"sub": "alice", "groups": ["sre"]}));
+        let caller = resolver.resolve(Some(&request(&[("authorization", &format!("Bearer {jwt}"))])), None);
+        assert_eq!(caller.token_id, None);
+        assert_eq!(caller.claims["sub"], "alice");
+
+        // Forged, expired, malformed and unknown tokens carry no identity
+        let forged = sign_jwt("wrong-key", serde_json::json!({"sub": "mallory"}));
+        let expired = sign_jwt("jwt-key", serde_json::json!({"sub": "alice", "exp": 1}));
+        let float_exp = sign_jwt("jwt-key", serde_json::json!({"sub": "alice", "exp": 1.7e9}));
+        let string_nbf = sign_jwt("jwt-key", serde_json::json!({"sub": "alice", "nbf": "0"}));
+        for token in [forged.as_str(), expired.as_str(), float_exp.as_str(), string_nbf.as_str(), "guess"] {
+            let caller = resolver.resolve(Some(&request(&[("authorization", &format!("Bearer {token}"))])), None);
+            assert_eq!(caller, CallerIdentity::default());
+        }
+    }
+
+    #[test]
+    fn test_jwt_issuer_and_audience() {
+        let config = IdentityConfig {
+            jwt_issuer: Some("https://sso.example.com".to_string()),
+            jwt_audience: Some("neurond".to_string()),
+            ..Default::default()
+        };
+        let resolver = IdentityResolver {
+            jwt_key: Some(hmac::Key::new(hmac::HMAC_SHA256, b"jwt-key")),
+            ..IdentityResolver::load(&config).unwrap()
+        };
+        let resolve = |claims: Value| {
+            let jwt = sign_jwt("jwt-key", claims);
+            resolver.resolve(Some(&request(&[("authorization", &format!("Bearer {jwt}"))])), None)
+        };
+
+        let iss = "https://sso.example.com";
+        assert_eq!(resolve(serde_json::json!({"sub": "alice", "iss": iss, "aud": "neurond"})).claims["sub"], "alice");
+        assert_eq!(resolve(serde_json::json!({"sub": "alice", "iss": iss, "aud": ["web", "neurond"]})).claims["sub"], "alice");
+        for claims in [
+            serde_json::json!({"sub": "alice", "aud": "neurond"}),
+            serde_json::json!({"sub": "alice", "iss": "https://evil.example.com", "aud": "neurond"}),
+            serde_json::json!({"sub": "alice", "iss": iss, "aud": "other"}),
+            serde_json::json!({"sub": "alice", "iss": iss}),
+        ] {
+            assert_eq!(resolve(claims), CallerIdentity::default());
+        }
+    }
+
+    #[test]
+    fn test_caller_match() {
+        let caller = CallerIdentity {
+            client_name: Some("cortexd".to_string()),
+            claims: serde_json::json!({"sub": "alice", "groups": ["dev", "sre"]})
+                .as_object()
+                .unwrap()
+                .clone(),
+            ..Default::default()
+        };
+        let rule = |toml: &str| toml::from_str::<CallerMatch>(toml).unwrap();
+
+        assert!(rule(r#"client_name = "cortex*""#).matches(&caller));
+        assert!(rule(r#"claims = { groups = "sre" }"#).matches(&caller));
+        assert!(!rule(r#"claims = { groups = "admin" }"#).matches(&caller));
+        assert!(!rule(r#"token_id = "ops-bot""#).matches(&caller));
+        assert!(!rule(r#"client_name = "cortexd"
+claims = { sub = "bob" }"#).matches(&caller));
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::security::identity::CallerMatch;
+use crate::security::policy::{Effect, Policy, PolicyRule, RuleMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The policy does not do what it says, e.g. a pattern that can never match
+    Error,
+    /// Likely a mistake, e.g. a rule that never takes effect
+    Warning,
+}
+
+/// One problem found in a policy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// ID of the rule the finding is about
+    pub rule: String,
+    pub message: String,
+}
+
+type Patterns = fn(&PolicyRule) -> &[String];
+
+/// Rule pattern lists, by the name of their field.
+const PATTERN_KINDS: [(&str, Patterns); 4] = [
+    ("tools", |rule| &rule.tools),
+    ("resources", |rule| &rule.resources),
+    ("prompts", |rule| &rule.prompts),
+    ("sampling", |rule| &rule.sampling),
+];
+
+/// Check a policy for patterns that can never match, allow and require_approval
+/// rules that an unconditional deny always overrides or that trust the spoofable
+/// client name, and, given the namespaced tool names of a live catalog, tool
+/// patterns that match none of them.
+pub fn lint(policy: &Policy, catalog: Option<&[String]>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut finding = |severity, rule: &PolicyRule, message: String| {
+        findings.push(Finding {
+            severity,
+            rule: rule.id.clone(),
+            message,
+        })
+    };
+
+    let mut ids = HashSet::new();
+    for rule in &policy.rules {
+        if !ids.insert(rule.id.as_str()) {
+            finding(Severity::Error, rule, "duplicate rule ID".to_string());
+        }
+
+        for (kind, patterns) in PATTERN_KINDS {
+            for pattern in patterns(rule) {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    finding(Severity::Error, rule, format!("invalid {} glob {:?}: {}", kind, pattern, e));
+                }
+            }
+        }
+        for condition in &rule.conditions {
+            for problem in condition.problems() {
+                finding(Severity::Error, rule, format!("condition on {:?}: {}", condition.pointer, problem));
+            }
+        }
+        for caller in &rule.callers {
+            let claims = caller.claims.iter().map(|(name, pattern)| (name.as_str(), pattern));
+            let fields = [
+                ("client_name", &caller.client_name),
+                ("token_id", &caller.token_id),
+                ("cert_subject", &caller.cert_subject),
+            ]
+            .into_iter()
+            .filter_map(|(name, pattern)| pattern.as_ref().map(|p| (name, p)));
+            for (name, pattern) in fields.chain(claims) {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    finding(Severity::Error, rule, format!("invalid caller {} glob {:?}: {}", name, pattern, e));
+                }
+            }
+        }
+        let by_name_only = |caller: &CallerMatch| {
+            caller.client_name.is_some()
+                && caller.token_id.is_none()
+                && caller.cert_subject.is_none()
+                && caller.claims.is_empty()
+        };
+        if rule.effect != Effect::Deny && rule.callers.iter().any(by_name_only) {
+            finding(
+                Severity::Warning,
+                rule,
+                "caller matched by client_name alone, which any client can claim".to_string(),
+            );
+        }
+
+        if rule.effect != Effect::Deny {
+            let shadowing = shadowing_denies(policy, rule, catalog);
+            if !shadowing.is_empty() {
+                finding(
+                    Severity::Warning,
+                    rule,
+                    format!("never takes effect: everything it matches is denied by {}", quoted(&shadowing)),
+                );
+            }
+        }
+
+        if let Some(catalog) = catalog {
+            for pattern in &rule.tools {
+                let Ok(glob) = glob::Pattern::new(pattern) else {
+                    continue;
+                };
+                if !catalog.iter().any(|tool| glob.matches(tool)) {
+                    finding(Severity::Warning, rule, format!("tool pattern {:?} matches no tool in the catalog", pattern));
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// IDs of the unconditional deny rules that together cover every pattern of
+/// `rule`, or none if some pattern may get past them.
+fn shadowing_denies<'a>(policy: &'a Policy, rule: &PolicyRule, catalog: Option<&[String]>) -> Vec<&'a str> {
+    let denies: Vec<&PolicyRule> = policy
+        .rules
+        .iter()
+        .filter(|deny| {
+            deny.effect == Effect::Deny
+                && deny.mode == RuleMode::Enforce
+                && deny.conditions.is_empty()
+                && deny.callers.is_empty()
+        })
+        .collect();
+
+    let mut shadowing = Vec::new();
+    let mut patterns = 0;
+    for (kind, kind_patterns) in PATTERN_KINDS {
+        // Invalid patterns match nothing, so they can't get past a deny either
+        for pattern in kind_patterns(rule).iter().filter(|p| glob::Pattern::new(p).is_ok()) {
+            patterns += 1;
+            let covering: Vec<&str> = denies
+                .iter()
+                .filter(|deny| kind_patterns(deny).iter().any(|d| covers(d, pattern)))
+                .map(|deny| deny.id.as_str())
+                .collect();
+            let covering = match (covering.is_empty(), kind, catalog) {
+                (true, "tools", Some(catalog)) => catalog_denies(&denies, pattern, catalog),
+                _ => covering,
+            };
+            if covering.is_empty() {
+                return Vec::new();
+            }
+            for id in covering {
+                if !shadowing.contains(&id) {
+                    shadowing.push(id);
+                }
+            }
+        }
+    }
+    if patterns == 0 {
+        return Vec::new();
+    }
+    shadowing
+}
+
+/// Deny rules that between them match every catalog tool `pattern` matches,
+/// or none if the pattern matches a tool they don't (or no tool at all).
+fn catalog_denies<'a>(denies: &[&'a PolicyRule], pattern: &str, catalog: &[String]) -> Vec<&'a str> {
+    let Ok(glob) = glob::Pattern::new(pattern) else {
+        return Vec::new();
+    };
+    let mut covering = Vec::new();
+    for tool in catalog.iter().filter(|tool| glob.matches(tool)) {
+        let denied_by = denies.iter().find(|deny| {
+            deny.tools
+                .iter()
+                .any(|d| glob::Pattern::new(d).is_ok_and(|d| d.matches(tool)))
+        });
+        match denied_by {
+            Some(deny) if !covering.contains(&deny.id.as_str()) => covering.push(deny.id.as_str()),
+            Some(_) => {}
+            None => return Vec::new(),
+        }
+    }
+    covering
+}
+
+/// Whether every name matching glob `pattern` also matches glob `deny`.
+///
+/// Only recognises equal patterns, literal names and `prefix*` denies, so it
+/// may miss a cover but never reports a false one.
+fn covers(deny: &str, pattern: &str) -> bool {
+    const META: &[char] = &['*', '?', '['];
+    if deny == pattern {
+        return true;
+    }
+    if !pattern.contains(META) {
+        return glob::Pattern::new(deny).is_ok_and(|d| d.matches(pattern));
+    }
+    match deny.strip_suffix('*') {
+        Some(prefix) if !prefix.contains(META) => pattern.starts_with(prefix),
+        _ => false,
+    }
+}
+
+fn quoted(ids: &[&str]) -> String {
+    ids.iter().map(|id| format!("{:?}", id)).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml: &str) -> Policy {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn messages(findings: &[Finding]) -> Vec<(Severity, &str, &str)> {
+        findings
+            .iter()
+            .map(|f| (f.severity, f.rule.as_str(), f.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let policy = policy(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "bad"
+            effect = "allow"
+            tools = ["linux.[system"]
+            conditions = [{ pointer = "/unit", regex = "(nginx" }]
+            callers = [{ claims = { groups = "[sre" } }]
+            [[rules]]
+            id = "bad"
+            effect = "deny"
+            prompts = ["linux.*"]
+            "#,
+        );
+        let findings = lint(&policy, None);
+        assert_eq!(findings.len(), 4);
+        assert!(findings.iter().all(|f| f.severity == Severity::Error && f.rule == "bad"));
+        assert!(findings[0].message.starts_with("invalid tools glob \"linux.[system\""));
+        assert!(findings[1].message.starts_with("condition on \"/unit\": invalid regex"));
+        assert!(findings[2].message.starts_with("invalid caller groups glob"));
+        assert_eq!(findings[3].message, "duplicate rule ID");
+    }
+
+    #[test]
+    fn test_shadowed_rules() {
+        let policy = policy(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "no-shell"
+            effect = "deny"
+            tools = ["linux.shell.*"]
+            [[rules]]
+            id = "no-restart"
+            effect = "deny"
+            tools = ["linux.service.restart"]
+            [[rules]]
+            id = "only-staging"
+            effect = "deny"
+            tools = ["*"]
+            callers = [{ client_name = "staging-*" }]
+            [[rules]]
+            id = "shell"
+            effect = "allow"
+            tools = ["linux.shell.exec*", "linux.service.restart"]
+            [[rules]]
+            id = "services"
+            effect = "require_approval"
+            tools = ["linux.service.*"]
+            [[rules]]
+            id = "read"
+            effect = "allow"
+            tools = ["linux.system.*"]
+            "#,
+        );
+        assert_eq!(
+            messages(&lint(&policy, None)),
+            vec![(
+                Severity::Warning,
+                "shell",
+                "never takes effect: everything it matches is denied by \"no-shell\", \"no-restart\"",
+            )]
+        );
+
+        // Against the catalog, "linux.service.*" only matches the denied restart tool
+        let catalog = vec!["linux.service.restart".to_string(), "linux.shell.exec".to_string()];
+        assert_eq!(
+            messages(&lint(&policy, Some(&catalog))),
+            vec![
+                (
+                    Severity::Warning,
+                    "shell",
+                    "never takes effect: everything it matches is denied by \"no-shell\", \"no-restart\"",
+                ),
+                (Severity::Warning, "services", "never takes effect: everything it matches is denied by \"no-restart\""),
+                (Severity::Warning, "read", "tool pattern \"linux.system.*\" matches no tool in the catalog"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_client_name_only_callers() {
+        let policy = policy(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "by-name"
+            effect = "allow"
+            tools = ["linux.*"]
+            callers = [{ token_id = "ops-bot" }, { client_name = "cortexd" }]
+            [[rules]]
+            id = "by-name-and-token"
+            effect = "require_approval"
+            tools = ["linux.*"]
+            callers = [{ client_name = "cortexd", token_id = "ops-bot" }]
+            [[rules]]
+            id = "deny-by-name"
+            effect = "deny"
+            tools = ["linux.shell.*"]
+            callers = [{ client_name = "cortexd" }]
+            "#,
+        );
+        assert_eq!(
+            messages(&lint(&policy, None)),
+            vec![(Severity::Warning, "by-name", "caller matched by client_name alone, which any client can claim")]
+        );
+    }
+}
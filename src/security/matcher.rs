@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::security::policy::{PolicyRule, RuleMode};
+
+/// Which pattern list of a rule a name is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    Tools,
+    Resources,
+    Prompts,
+    Sampling,
+}
+
+impl PatternKind {
+    const ALL: [PatternKind; 4] = [Self::Tools, Self::Resources, Self::Prompts, Self::Sampling];
+
+    pub fn patterns(self, rule: &PolicyRule) -> &[String] {
+        match self {
+            PatternKind::Tools => &rule.tools,
+            PatternKind::Resources => &rule.resources,
+            PatternKind::Prompts => &rule.prompts,
+            PatternKind::Sampling => &rule.sampling,
+        }
+    }
+}
+
+/// (rule index, pattern index within the rule's list)
+type Entry = (usize, usize);
+
+/// The patterns of the enforced rules, parsed once.
+///
+/// Literal patterns are found by hash lookup and `prefix*` patterns by walking
+/// a trie along the name; only the remaining globs are tried one by one.
+/// Invalid globs are dropped, as they never match.
+#[derive(Debug, Default)]
+pub struct PolicyIndex {
+    /// By `PatternKind`
+    kinds: [PatternIndex; 4],
+}
+
+impl PolicyIndex {
+    pub fn new(rules: &[PolicyRule]) -> Self {
+        let mut index = Self::default();
+        for (i, rule) in rules.iter().enumerate() {
+            if rule.mode != RuleMode::Enforce {
+                continue;
+            }
+            for kind in PatternKind::ALL {
+                for (j, pattern) in kind.patterns(rule).iter().enumerate() {
+                    index.kinds[kind as usize].insert(pattern, (i, j));
+                }
+            }
+        }
+        index
+    }
+
+    /// For each enforced rule with a `kind` pattern matching `name`, in rule
+    /// order, the index of the rule and of its first such pattern.
+    pub fn matching(&self, kind: PatternKind, name: &str) -> Vec<(usize, usize)> {
+        self.kinds[kind as usize].matching(name)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PatternIndex {
+    exact: HashMap<String, Vec<Entry>>,
+    prefixes: PrefixTrie,
+    globs: Vec<(glob::Pattern, Entry)>,
+}
+
+impl PatternIndex {
+    fn insert(&mut self, pattern: &str, entry: Entry) {
+        const META: &[char] = &['*', '?', '['];
+        if !pattern.contains(META) {
+            self.exact.entry(pattern.to_string()).or_default().push(entry);
+            return;
+        }
+        match pattern.strip_suffix('*') {
+            Some(prefix) if !prefix.contains(META) => self.prefixes.insert(prefix, entry),
+            _ => {
+                if let Ok(glob) = glob::Pattern::new(pattern) {
+                    self.globs.push((glob, entry));
+                }
+            }
+        }
+    }
+
+    fn matching(&self, name: &str) -> Vec<Entry> {
+        let mut entries = self.exact.get(name).cloned().unwrap_or_default();
+        self.prefixes.collect(name, &mut entries);
+        entries.extend(self.globs.iter().filter(|(glob, _)| glob.matches(name)).map(|(_, entry)| *entry));
+
+        // Keep the first matching pattern of each rule
+        entries.sort_unstable();
+        entries.dedup_by_key(|(rule, _)| *rule);
+        entries
+    }
+}
+
+/// Byte trie of `prefix*` patterns; a node holds the entries whose prefix ends there.
+#[derive(Debug, Default)]
+struct PrefixTrie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+    entries: Vec<Entry>,
+}
+
+impl PrefixTrie {
+    fn insert(&mut self, prefix: &str, entry: Entry) {
+        if self.nodes.is_empty() {
+            self.nodes.push(TrieNode::default());
+        }
+        let mut node = 0;
+        for byte in prefix.bytes() {
+            node = match self.nodes[node].children.get(&byte) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(byte, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].entries.push(entry);
+    }
+
+    /// Add the entries of every prefix of `name` to `out`.
+    fn collect(&self, name: &str, out: &mut Vec<Entry>) {
+        let Some(root) = self.nodes.first() else {
+            return;
+        };
+        out.extend_from_slice(&root.entries);
+        let mut node = root;
+        for byte in name.bytes() {
+            match node.children.get(&byte) {
+                Some(&child) => {
+                    node = &self.nodes[child];
+                    out.extend_from_slice(&node.entries);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::policy::Policy;
+
+    #[test]
+    fn test_index_matches_like_globs() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+            rules = [
+                { id = "a", effect = "allow", tools = ["linux.system.cpu", "linux.*"] },
+                { id = "b", effect = "deny", tools = ["linux.s?stem.*", "*"] },
+                { id = "c", effect = "deny", tools = ["linux.[bad", "linux.sys*"], mode = "audit" },
+                { id = "d", effect = "allow", tools = ["linux.system.*"], prompts = ["linux.system.cpu"] },
+            ]
+            "#,
+        )
+        .unwrap();
+        let index = PolicyIndex::new(&policy.rules);
+
+        assert_eq!(index.matching(PatternKind::Tools, "linux.system.cpu"), vec![(0, 0), (1, 0), (3, 0)]);
+        assert_eq!(index.matching(PatternKind::Tools, "linux.service.list"), vec![(0, 1), (1, 1)]);
+        assert_eq!(index.matching(PatternKind::Tools, "redis.get"), vec![(1, 1)]);
+        assert_eq!(index.matching(PatternKind::Prompts, "linux.system.cpu"), vec![(3, 0)]);
+        assert!(index.matching(PatternKind::Resources, "linux.system.cpu").is_empty());
+    }
+}
@@ -1,2 +1,7 @@
+pub mod approval;
+pub mod condition;
+pub mod identity;
+pub mod lint;
+pub mod matcher;
 pub mod policy;
 pub mod audit;
@@ -1,28 +1,138 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fs;
+use std::ops::Deref;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::security::condition::{ArgumentCondition, ConditionPatterns};
+use crate::security::identity::{CallerIdentity, CallerMatch, CallerPatterns};
+use crate::security::matcher::{PatternKind, PolicyIndex};
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum Effect {
     Allow,
     #[default]
     Deny,
+    /// Hold tool calls until a human approves them; anything else fails closed
+    RequireApproval,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Whether a rule takes part in decisions or only in shadow evaluation.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMode {
+    #[default]
+    Enforce,
+    /// Evaluated in the candidate policy only; disagreements are audited
+    Audit,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct PolicyRule {
     pub id: String,
     pub description: Option<String>,
     pub effect: Effect,
+    #[serde(default)]
+    pub mode: RuleMode,
+    #[serde(default)]
     pub tools: Vec<String>,
+    /// Namespaced resource URI globs (e.g. "linux.file:///var/log/*") for `resources/read`
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// Namespaced prompt name globs (e.g. "linux.runbook-*") for `prompts/get`
+    #[serde(default)]
+    pub prompts: Vec<String>,
+    /// Namespace globs (e.g. "linux") whose downstreams may request `sampling/createMessage`
+    #[serde(default)]
+    pub sampling: Vec<String>,
+    /// Tool call arguments that must all satisfy their tests for the rule's `tools` to match
+    #[serde(default)]
+    pub conditions: Vec<ArgumentCondition>,
+    /// Upstream callers the rule applies to, any one of which must match; empty means every caller
+    #[serde(default)]
+    pub callers: Vec<CallerMatch>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Policy {
     pub default_action: Effect,
     #[serde(default)]
     pub rules: Vec<PolicyRule>,
+    /// Set by [`compile`](Self::compile); without it every pattern is parsed on each decision
+    #[serde(skip)]
+    compiled: Option<Arc<Compiled>>,
+}
+
+/// Lookup structures built once by [`Policy::compile`].
+#[derive(Debug)]
+struct Compiled {
+    index: PolicyIndex,
+    /// By rule index
+    rules: Vec<RulePatterns>,
+    candidate: Option<Arc<Policy>>,
+}
+
+/// The condition and caller patterns of one rule, parsed once.
+#[derive(Debug)]
+struct RulePatterns {
+    conditions: Vec<ConditionPatterns>,
+    callers: Vec<CallerPatterns>,
+}
+
+impl RulePatterns {
+    fn new(rule: &PolicyRule) -> Self {
+        Self {
+            conditions: rule.conditions.iter().map(ArgumentCondition::patterns).collect(),
+            callers: rule.callers.iter().map(CallerMatch::patterns).collect(),
+        }
+    }
+}
+
+/// A rule, with its patterns if the policy is compiled.
+#[derive(Clone, Copy)]
+struct RuleRef<'a> {
+    rule: &'a PolicyRule,
+    patterns: Option<&'a RulePatterns>,
+}
+
+impl Deref for RuleRef<'_> {
+    type Target = PolicyRule;
+
+    fn deref(&self) -> &PolicyRule {
+        self.rule
+    }
+}
+
+impl RuleRef<'_> {
+    fn applies_to(&self, caller: &CallerIdentity) -> bool {
+        let callers = &self.rule.callers;
+        callers.is_empty()
+            || match self.patterns {
+                Some(patterns) => callers
+                    .iter()
+                    .zip(&patterns.callers)
+                    .any(|(c, p)| c.matches_with(caller, p)),
+                None => callers.iter().any(|c| c.matches(caller)),
+            }
+    }
+
+    /// Whether `arguments` satisfy every condition, counting one that cannot
+    /// be evaluated as `unevaluable`.
+    fn conditions_hold(&self, arguments: &serde_json::Value, unevaluable: bool) -> bool {
+        let conditions = &self.rule.conditions;
+        let holds = |result: Option<bool>| result.unwrap_or(unevaluable);
+        match self.patterns {
+            Some(patterns) => conditions
+                .iter()
+                .zip(&patterns.conditions)
+                .all(|(c, p)| holds(c.evaluate_with(arguments, p))),
+            None => conditions.iter().all(|c| holds(c.evaluate(arguments))),
+        }
+    }
 }
 
 impl Default for Policy {
@@ -30,40 +140,331 @@ impl Default for Policy {
         Self {
             default_action: Effect::Deny,
             rules: Vec::new(),
+            compiled: None,
         }
     }
 }
 
 impl Policy {
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Self::load_with_hash(path).map(|(policy, _)| policy)
+    }
+
+    /// Load a policy file along with the SHA-256 of its content.
+    pub fn load_with_hash<P: AsRef<Path>>(path: P) -> Result<(Self, String), String> {
         let content =
             fs::read_to_string(path).map_err(|e| format!("Failed to read policy file: {}", e))?;
-        toml::from_str(&content).map_err(|e| format!("Failed to parse policy TOML: {}", e))
-    }
-
-    /// Check if a tool is allowed by the policy
-    pub fn is_allowed(&self, tool_name: &str) -> bool {
-        let mut matched_allow = false;
-
-        for rule in &self.rules {
-            for pattern in &rule.tools {
-                if wildcard_match(pattern, tool_name) {
-                    if rule.effect == Effect::Deny {
-                        // Deny-wins: Short-circuit immediately on ANY explicit deny
-                        return false;
-                    } else if rule.effect == Effect::Allow {
-                        matched_allow = true;
-                    }
-                }
+        let policy =
+            toml::from_str(&content).map_err(|e| format!("Failed to parse policy TOML: {}", e))?;
+        Ok((policy, content_hash(&content)))
+    }
+
+    /// Index the rules, and those of the candidate policy, for evaluation, and
+    /// parse their condition and caller patterns.
+    ///
+    /// [`PolicyStore`] does this for every policy it holds. The rules must not
+    /// change afterwards.
+    pub fn compile(mut self) -> Self {
+        self.compiled = None;
+        let candidate = self
+            .candidate()
+            .map(|candidate| Arc::new(Arc::unwrap_or_clone(candidate).compile()));
+        self.compiled = Some(Arc::new(Compiled {
+            index: PolicyIndex::new(&self.rules),
+            rules: self.rules.iter().map(RulePatterns::new).collect(),
+            candidate,
+        }));
+        self
+    }
+
+    /// The policy as it would be with every `mode = "audit"` rule enforced,
+    /// or None if there are no such rules.
+    pub fn candidate(&self) -> Option<Arc<Policy>> {
+        if let Some(compiled) = &self.compiled {
+            return compiled.candidate.clone();
+        }
+        if self.rules.iter().all(|rule| rule.mode == RuleMode::Enforce) {
+            return None;
+        }
+        let mut candidate = self.clone();
+        for rule in &mut candidate.rules {
+            rule.mode = RuleMode::Enforce;
+        }
+        Some(Arc::new(candidate))
+    }
+
+    /// Check if a tool call by `caller` is allowed outright by the policy.
+    pub fn is_allowed(&self, tool_name: &str, arguments: &serde_json::Value, caller: &CallerIdentity) -> bool {
+        self.decide_tool(tool_name, arguments, caller).is_allowed()
+    }
+
+    /// Decide a tool call by `caller`: allow, deny, or hold it for approval.
+    ///
+    /// A rule with `conditions` only matches when `arguments` satisfy all of them.
+    /// A condition that cannot be evaluated, e.g. on a missing argument, fails
+    /// closed: it holds for deny rules and not for the others.
+    pub fn decide_tool(&self, tool_name: &str, arguments: &serde_json::Value, caller: &CallerIdentity) -> PolicyDecision {
+        self.evaluate_when(tool_name, PatternKind::Tools, |rule| {
+            rule.applies_to(caller) && rule.conditions_hold(arguments, rule.effect == Effect::Deny)
+        })
+    }
+
+    /// Whether some call to the tool by `caller` could get past the policy, i.e.
+    /// it is not denied outright. Used to hide tools from `tools/list`.
+    ///
+    /// Conditions are assumed to go the caller's way: conditional allow rules
+    /// count, conditional deny rules don't.
+    pub fn is_tool_visible(&self, tool_name: &str, caller: &CallerIdentity) -> bool {
+        let decision = self.evaluate_when(tool_name, PatternKind::Tools, |rule| {
+            rule.applies_to(caller) && (rule.conditions.is_empty() || rule.effect != Effect::Deny)
+        });
+        decision.effect != Effect::Deny
+    }
+
+    /// Check if reading a (namespaced) resource URI is allowed by the policy
+    pub fn is_resource_allowed(&self, uri: &str, caller: &CallerIdentity) -> bool {
+        self.decide_resource(uri, caller).is_allowed()
+    }
+
+    /// Decide a resource read; only `allow` lets it through.
+    ///
+    /// The URI is matched with its `.` and `..` path segments resolved, so
+    /// "/var/log/../../etc/shadow" is judged as "/etc/shadow". URIs with
+    /// percent-encoded dots or slashes are denied outright, as what they
+    /// resolve to depends on whether the downstream decodes them.
+    pub fn decide_resource(&self, uri: &str, caller: &CallerIdentity) -> PolicyDecision {
+        match normalize_uri(uri) {
+            Some(uri) => self.evaluate(&uri, PatternKind::Resources, caller),
+            None => PolicyDecision {
+                effect: Effect::Deny,
+                matched: Vec::new(),
+                default_applied: false,
+            },
+        }
+    }
+
+    /// Check if fetching a (namespaced) prompt is allowed by the policy
+    pub fn is_prompt_allowed(&self, prompt_name: &str, caller: &CallerIdentity) -> bool {
+        self.decide_prompt(prompt_name, caller).is_allowed()
+    }
+
+    /// Decide a prompt fetch; only `allow` lets it through.
+    pub fn decide_prompt(&self, prompt_name: &str, caller: &CallerIdentity) -> PolicyDecision {
+        self.evaluate(prompt_name, PatternKind::Prompts, caller)
+    }
+
+    /// Check if downstreams in `namespace` may ask `caller` for an LLM completion
+    pub fn is_sampling_allowed(&self, namespace: &str, caller: &CallerIdentity) -> bool {
+        self.decide_sampling(namespace, caller).is_allowed()
+    }
+
+    /// Decide a sampling request from `namespace`; only `allow` lets it through.
+    pub fn decide_sampling(&self, namespace: &str, caller: &CallerIdentity) -> PolicyDecision {
+        self.evaluate(namespace, PatternKind::Sampling, caller)
+    }
+
+    /// Evaluate `name` against the pattern list selected from each rule that
+    /// applies to `caller`.
+    fn evaluate(&self, name: &str, kind: PatternKind, caller: &CallerIdentity) -> PolicyDecision {
+        self.evaluate_when(name, kind, |rule| rule.applies_to(caller))
+    }
+
+    /// Decide `name` over the enforced rules for which `applies` is true. Deny
+    /// wins, then require_approval, then allow; with no match the default action applies.
+    fn evaluate_when(
+        &self,
+        name: &str,
+        kind: PatternKind,
+        applies: impl Fn(RuleRef) -> bool,
+    ) -> PolicyDecision {
+        let mut matched = Vec::new();
+
+        for (rule, pattern) in self.matching(name, kind) {
+            if applies(rule) {
+                matched.push(RuleMatch {
+                    id: rule.id.clone(),
+                    effect: rule.effect,
+                    pattern: pattern.clone(),
+                });
             }
         }
 
-        if matched_allow {
-            true
-        } else {
-            self.default_action == Effect::Allow
+        let effect = [Effect::Deny, Effect::RequireApproval, Effect::Allow]
+            .into_iter()
+            .find(|effect| matched.iter().any(|m| m.effect == *effect));
+        PolicyDecision {
+            effect: effect.unwrap_or(self.default_action),
+            default_applied: effect.is_none(),
+            matched,
         }
     }
+
+    /// Each enforced rule with a `kind` pattern matching `name`, in policy
+    /// order, with the first such pattern.
+    fn matching(&self, name: &str, kind: PatternKind) -> Vec<(RuleRef<'_>, &String)> {
+        if let Some(compiled) = &self.compiled {
+            return compiled
+                .index
+                .matching(kind, name)
+                .into_iter()
+                .map(|(i, pattern)| {
+                    let rule = &self.rules[i];
+                    let patterns = Some(&compiled.rules[i]);
+                    (RuleRef { rule, patterns }, &kind.patterns(rule)[pattern])
+                })
+                .collect();
+        }
+        self.rules
+            .iter()
+            .filter(|rule| rule.mode == RuleMode::Enforce)
+            .filter_map(|rule| {
+                let pattern = kind.patterns(rule).iter().find(|p| wildcard_match(p, name))?;
+                Some((RuleRef { rule, patterns: None }, pattern))
+            })
+            .collect()
+    }
+}
+
+/// Why the policy decided a request the way it did.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyDecision {
+    pub effect: Effect,
+    /// Rules that matched, in policy order
+    pub matched: Vec<RuleMatch>,
+    /// No rule matched, so `default_action` decided
+    pub default_applied: bool,
+}
+
+impl PolicyDecision {
+    pub fn is_allowed(&self) -> bool {
+        self.effect == Effect::Allow
+    }
+}
+
+/// A rule that matched a request, and the pattern it matched with.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleMatch {
+    pub id: String,
+    pub effect: Effect,
+    pub pattern: String,
+}
+
+/// Atomically swappable handle to the active policy.
+///
+/// Each request takes a snapshot with [`load`](Self::load), so a reload never
+/// changes the rules half-way through one decision. Clones share the same policy.
+#[derive(Clone)]
+pub struct PolicyStore {
+    current: Arc<RwLock<ActivePolicy>>,
+}
+
+struct ActivePolicy {
+    policy: Arc<Policy>,
+    /// SHA-256 of the file the policy was read from; None for the built-in default
+    hash: Option<String>,
+}
+
+impl PolicyStore {
+    pub fn new(policy: Policy, hash: Option<String>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(ActivePolicy {
+                policy: Arc::new(policy.compile()),
+                hash,
+            })),
+        }
+    }
+
+    /// Snapshot of the policy currently in effect.
+    pub fn load(&self) -> Arc<Policy> {
+        match self.current.read() {
+            Ok(active) => active.policy.clone(),
+            Err(poisoned) => poisoned.into_inner().policy.clone(),
+        }
+    }
+
+    /// Content hash of the policy currently in effect.
+    pub fn hash(&self) -> Option<String> {
+        match self.current.read() {
+            Ok(active) => active.hash.clone(),
+            Err(poisoned) => poisoned.into_inner().hash.clone(),
+        }
+    }
+
+    /// Put `policy` into effect, returning the hash of the one it replaces.
+    pub fn swap(&self, policy: Policy, hash: String) -> Option<String> {
+        let mut active = match self.current.write() {
+            Ok(active) => active,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        active.policy = Arc::new(policy.compile());
+        active.hash.replace(hash)
+    }
+}
+
+impl From<Policy> for PolicyStore {
+    fn from(policy: Policy) -> Self {
+        Self::new(policy, None)
+    }
+}
+
+/// Hex SHA-256 of policy file content, as recorded in the audit log.
+pub fn content_hash(content: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, content.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `uri` with the dot segments of its path removed (RFC 3986 §5.2.4), or None
+/// if the path has percent-encoded dots, slashes or backslashes.
+fn normalize_uri(uri: &str) -> Option<Cow<'_, str>> {
+    // scheme ":" ["//" authority] path ["?" query | "#" fragment]
+    let path_start = match uri.find(':') {
+        Some(colon) => match uri[colon + 1..].strip_prefix("//") {
+            Some(rest) => colon + 3 + rest.find('/').unwrap_or(rest.len()),
+            None => colon + 1,
+        },
+        None => 0,
+    };
+    let path_end = uri[path_start..].find(['?', '#']).map_or(uri.len(), |end| path_start + end);
+    let path = &uri[path_start..path_end];
+
+    let lower = path.to_ascii_lowercase();
+    if ["%2e", "%2f", "%5c"].iter().any(|encoded| lower.contains(encoded)) {
+        return None;
+    }
+    if !path.split('/').any(|segment| segment == "." || segment == "..") {
+        return Some(Cow::Borrowed(uri));
+    }
+
+    let (root, relative) = match path.strip_prefix('/') {
+        Some(relative) => ("/", relative),
+        None => ("", path),
+    };
+    let mut resolved: Vec<&str> = Vec::new();
+    let mut segments = relative.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        match segment {
+            "." | ".." => {
+                if segment == ".." {
+                    resolved.pop();
+                }
+                // Keep the trailing slash of "dir/.."
+                if segments.peek().is_none() {
+                    resolved.push("");
+                }
+            }
+            segment => resolved.push(segment),
+        }
+    }
+    Some(Cow::Owned(format!(
+        "{}{}{}{}",
+        &uri[..path_start],
+        root,
+        resolved.join("/"),
+        &uri[path_end..]
+    )))
 }
 
 fn wildcard_match(pattern: &str, value: &str) -> bool {
@@ -76,6 +477,10 @@ fn wildcard_match(pattern: &str, value: &str) -> bool {
 mod tests {
     use super::*;
 
+    fn anyone() -> CallerIdentity {
+        CallerIdentity::default()
+    }
+
     #[test]
     fn test_wildcard_match() {
         assert!(wildcard_match("system.*", "system.cpu"));
@@ -93,19 +498,22 @@ mod tests {
                     description: None,
                     effect: Effect::Allow,
                     tools: vec!["system.*".into()],
+                    ..Default::default()
                 },
                 PolicyRule {
                     id: "deny-cpu".into(),
                     description: None,
                     effect: Effect::Deny,
                     tools: vec!["system.cpu".into()],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
-        assert!(policy.is_allowed("system.memory")); // Allowed by system.*
-        assert!(!policy.is_allowed("system.cpu")); // Denied by system.cpu override
-        assert!(!policy.is_allowed("service.list")); // Denied by default
+        assert!(policy.is_allowed("system.memory", &serde_json::json!({}), &anyone())); // Allowed by system.*
+        assert!(!policy.is_allowed("system.cpu", &serde_json::json!({}), &anyone())); // Denied by system.cpu override
+        assert!(!policy.is_allowed("service.list", &serde_json::json!({}), &anyone())); // Denied by default
     }
 
     #[test]
@@ -119,18 +527,21 @@ mod tests {
                     description: None,
                     effect: Effect::Deny,
                     tools: vec!["network.*".into()],
+                    ..Default::default()
                 },
                 PolicyRule {
                     id: "allow-specific-ping-override".into(),
                     description: None,
                     effect: Effect::Allow,
                     tools: vec!["network.ping".into()],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         // Deny wins!
-        assert!(!policy.is_allowed("network.ping"));
+        assert!(!policy.is_allowed("network.ping", &serde_json::json!({}), &anyone()));
     }
 
     #[test]
@@ -142,6 +553,447 @@ mod tests {
         assert!(!wildcard_match("redis.?et", "redis.keys"));
     }
 
+    #[test]
+    fn test_resource_rules_are_separate_from_tool_rules() {
+        let policy = Policy {
+            default_action: Effect::Deny,
+            rules: vec![PolicyRule {
+                id: "allow-logs".into(),
+                effect: Effect::Allow,
+                tools: vec!["linux.*".into()],
+                resources: vec!["linux.file:///var/log/*".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert!(policy.is_resource_allowed("linux.file:///var/log/syslog", &anyone()));
+        assert!(!policy.is_resource_allowed("linux.file:///etc/shadow", &anyone()));
+        // Tool patterns never grant resource reads
+        assert!(!policy.is_resource_allowed("linux.system", &anyone()));
+    }
+
+    #[test]
+    fn test_resource_uri_traversal() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "allow-logs"
+            effect = "allow"
+            resources = ["linux.file:///var/log/*"]
+            [[rules]]
+            id = "deny-secrets"
+            effect = "deny"
+            resources = ["linux.file:///var/log/secret/*"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(!policy.is_resource_allowed("linux.file:///var/log/../../etc/shadow", &anyone()));
+        assert!(!policy.is_resource_allowed("linux.file:///var/log/./secret/key", &anyone()));
+        assert!(!policy.is_resource_allowed("linux.file:///var/log/x/../secret/key", &anyone()));
+        assert!(!policy.is_resource_allowed("linux.file:///var/log/%2e%2e/%2E%2E/etc/shadow", &anyone()));
+        assert!(!policy.is_resource_allowed("linux.file:///var/log/..%2f..%2fetc/shadow", &anyone()));
+        assert!(policy.is_resource_allowed("linux.file:///var/log/nginx/../syslog", &anyone()));
+        assert!(policy.is_resource_allowed("linux.file:///var/log/syslog?lines=10", &anyone()));
+
+        assert_eq!(
+            normalize_uri("linux.file://host/a/b/../../../c/./d/..?q=/../x").unwrap(),
+            "linux.file://host/c/?q=/../x"
+        );
+        assert_eq!(normalize_uri("ops.memo:notes/../todo").unwrap(), "ops.memo:todo");
+    }
+
+    #[test]
+    fn test_prompt_rules() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "allow-runbooks"
+        effect = "allow"
+        prompts = ["ops.runbook-*"]
+
+        [[rules]]
+        id = "deny-destructive-runbook"
+        effect = "deny"
+        prompts = ["ops.runbook-wipe"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        assert!(policy.is_prompt_allowed("ops.runbook-disk", &anyone()));
+        assert!(!policy.is_prompt_allowed("ops.runbook-wipe", &anyone()));
+        assert!(!policy.is_allowed("ops.runbook-disk", &serde_json::json!({}), &anyone()));
+    }
+
+    #[test]
+    fn test_sampling_rules_match_namespaces() {
+        let toml = r#"
+        default_action = "allow"
+
+        [[rules]]
+        id = "no-sampling-from-untrusted"
+        effect = "deny"
+        sampling = ["thirdparty-*"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        assert!(policy.is_sampling_allowed("linux", &anyone()));
+        assert!(!policy.is_sampling_allowed("thirdparty-search", &anyone()));
+        // Sampling patterns never affect tool calls
+        assert!(policy.is_allowed("thirdparty-search.query", &serde_json::json!({}), &anyone()));
+    }
+
+    #[test]
+    fn test_argument_conditions() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "allow-nginx-restart"
+        effect = "allow"
+        tools = ["linux.service.restart"]
+        conditions = [{ pointer = "/unit", glob = "nginx*" }]
+
+        [[rules]]
+        id = "allow-log-reads"
+        effect = "allow"
+        tools = ["linux.file.read"]
+        conditions = [{ pointer = "/path", path_prefix = "/var/log" }]
+
+        [[rules]]
+        id = "deny-huge-reads"
+        effect = "deny"
+        tools = ["linux.file.read"]
+        conditions = [{ pointer = "/max_bytes", min = 1048577 }]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let call = |tool: &str, args: serde_json::Value| policy.is_allowed(tool, &args, &anyone());
+
+        assert!(call("linux.service.restart", serde_json::json!({"unit": "nginx.service"})));
+        assert!(!call("linux.service.restart", serde_json::json!({"unit": "sshd.service"})));
+        assert!(!call("linux.service.restart", serde_json::json!({})));
+
+        assert!(call("linux.file.read", serde_json::json!({"path": "/var/log/syslog", "max_bytes": 4096})));
+        assert!(!call("linux.file.read", serde_json::json!({"path": "/etc/shadow", "max_bytes": 4096})));
+        // Deny conditions fail closed on a missing or mistyped argument
+        assert!(!call("linux.file.read", serde_json::json!({"path": "/var/log/syslog"})));
+        assert!(!call("linux.file.read", serde_json::json!({"path": "/var/log/syslog", "max_bytes": "4096"})));
+        // Deny conditions still win over a matching allow
+        assert!(!call(
+            "linux.file.read",
+            serde_json::json!({"path": "/var/log/syslog", "max_bytes": 10485760})
+        ));
+    }
+
+    #[test]
+    fn test_caller_rules() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "read-only-for-everyone"
+        effect = "allow"
+        tools = ["linux.system.*"]
+
+        [[rules]]
+        id = "restarts-for-sre"
+        effect = "allow"
+        tools = ["linux.service.restart"]
+        callers = [{ claims = { groups = "sre" } }, { token_id = "ops-bot" }]
+
+        [[rules]]
+        id = "no-restarts-from-ide"
+        effect = "deny"
+        tools = ["linux.service.restart"]
+        callers = [{ client_name = "vscode*" }]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let sre = CallerIdentity {
+            claims: serde_json::json!({"groups": ["sre"]}).as_object().unwrap().clone(),
+            ..Default::default()
+        };
+        let bot = CallerIdentity {
+            token_id: Some("ops-bot".into()),
+            ..Default::default()
+        };
+        let sre_in_ide = CallerIdentity {
+            client_name: Some("vscode-mcp".into()),
+            ..sre.clone()
+        };
+        let args = serde_json::json!({});
+
+        assert!(policy.is_allowed("linux.system.cpu", &args, &anyone()));
+        assert!(!policy.is_allowed("linux.service.restart", &args, &anyone()));
+        assert!(policy.is_allowed("linux.service.restart", &args, &sre));
+        assert!(policy.is_allowed("linux.service.restart", &args, &bot));
+        assert!(!policy.is_allowed("linux.service.restart", &args, &sre_in_ide));
+    }
+
+    #[test]
+    fn test_require_approval_ranks_between_deny_and_allow() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "allow-services"
+        effect = "allow"
+        tools = ["linux.service.*"]
+        resources = ["linux.file:///etc/*"]
+
+        [[rules]]
+        id = "approve-restarts"
+        effect = "require_approval"
+        tools = ["linux.service.restart", "linux.service.stop"]
+        resources = ["linux.file:///etc/*"]
+
+        [[rules]]
+        id = "never-stop"
+        effect = "deny"
+        tools = ["linux.service.stop"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let effect = |tool: &str| policy.decide_tool(tool, &serde_json::json!({}), &anyone()).effect;
+
+        assert_eq!(effect("linux.service.status"), Effect::Allow);
+        assert_eq!(effect("linux.service.restart"), Effect::RequireApproval);
+        assert_eq!(effect("linux.service.stop"), Effect::Deny);
+        assert!(!policy.is_allowed("linux.service.restart", &serde_json::json!({}), &anyone()));
+        // Only tool calls can be held; other requests fail closed
+        assert!(!policy.is_resource_allowed("linux.file:///etc/hosts", &anyone()));
+    }
+
+    #[test]
+    fn test_tool_visibility() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "allow-linux"
+        effect = "allow"
+        tools = ["linux.*"]
+
+        [[rules]]
+        id = "no-shell"
+        effect = "deny"
+        tools = ["linux.shell.*"]
+
+        [[rules]]
+        id = "protect-sshd"
+        effect = "deny"
+        tools = ["linux.service.restart"]
+        conditions = [{ pointer = "/unit", glob = "sshd*" }]
+
+        [[rules]]
+        id = "nginx-only"
+        effect = "allow"
+        tools = ["web.reload"]
+        conditions = [{ pointer = "/unit", glob = "nginx*" }]
+
+        [[rules]]
+        id = "approve-reboots"
+        effect = "require_approval"
+        tools = ["linux.system.reboot"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let visible = |tool: &str| policy.is_tool_visible(tool, &anyone());
+
+        assert!(visible("linux.system.cpu"));
+        assert!(!visible("linux.shell.exec"));
+        assert!(!visible("redis.get"));
+        // Condition-dependent and approval-gated tools stay visible
+        assert!(visible("linux.service.restart"));
+        assert!(visible("web.reload"));
+        assert!(visible("linux.system.reboot"));
+    }
+
+    #[test]
+    fn test_decision_explains_matches() {
+        let toml = r#"
+        default_action = "deny"
+
+        [[rules]]
+        id = "allow-linux"
+        effect = "allow"
+        tools = ["redis.*", "linux.*"]
+
+        [[rules]]
+        id = "no-shell"
+        effect = "deny"
+        tools = ["linux.shell.*"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let decide = |tool: &str| policy.decide_tool(tool, &serde_json::json!({}), &anyone());
+
+        let denied = decide("linux.shell.exec");
+        assert_eq!(denied.effect, Effect::Deny);
+        assert!(!denied.default_applied);
+        assert_eq!(
+            denied.matched,
+            vec![
+                RuleMatch { id: "allow-linux".into(), effect: Effect::Allow, pattern: "linux.*".into() },
+                RuleMatch { id: "no-shell".into(), effect: Effect::Deny, pattern: "linux.shell.*".into() },
+            ]
+        );
+
+        let unmatched = decide("docker.ps");
+        assert_eq!(unmatched.effect, Effect::Deny);
+        assert!(unmatched.default_applied);
+        assert!(unmatched.matched.is_empty());
+        assert_eq!(
+            serde_json::to_value(&unmatched).unwrap(),
+            serde_json::json!({"effect": "deny", "matched": [], "default_applied": true})
+        );
+    }
+
+    #[test]
+    fn test_audit_mode_rules_only_shape_the_candidate() {
+        let toml = r#"
+        default_action = "allow"
+
+        [[rules]]
+        id = "no-shell"
+        effect = "deny"
+        tools = ["linux.shell.*"]
+
+        [[rules]]
+        id = "tighten-restarts"
+        effect = "deny"
+        mode = "audit"
+        tools = ["linux.service.restart"]
+        "#;
+
+        let policy: Policy = toml::from_str(toml).unwrap();
+        let args = serde_json::json!({});
+        assert!(policy.is_allowed("linux.service.restart", &args, &anyone()));
+
+        let candidate = policy.candidate().unwrap();
+        assert!(!candidate.is_allowed("linux.service.restart", &args, &anyone()));
+        assert!(!candidate.is_allowed("linux.shell.exec", &args, &anyone()));
+        assert!(candidate.candidate().is_none());
+    }
+
+    #[test]
+    fn test_compiled_policy_decides_like_uncompiled() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+            [[rules]]
+            id = "read"
+            effect = "allow"
+            tools = ["linux.system.*", "linux.service.list", "redis.?et"]
+            resources = ["linux.file:///var/log/*"]
+            prompts = ["linux.runbook-*"]
+            sampling = ["linux"]
+            [[rules]]
+            id = "services"
+            effect = "require_approval"
+            tools = ["linux.service.*", "linux.[bad"]
+            conditions = [{ pointer = "/unit", glob = "*.service" }]
+            [[rules]]
+            id = "staging"
+            effect = "deny"
+            tools = ["*"]
+            callers = [{ client_name = "staging-*" }]
+            [[rules]]
+            id = "no-reboot"
+            effect = "deny"
+            mode = "audit"
+            tools = ["linux.system.reboot"]
+            [[rules]]
+            id = "sre-restarts"
+            effect = "allow"
+            tools = ["linux.service.restart"]
+            conditions = [{ pointer = "/unit", regex = "(nginx|redis)\\.service" }]
+            callers = [{ claims = { groups = "sre-*" } }, { token_id = "[bad" }]
+            [[rules]]
+            id = "broken-condition"
+            effect = "deny"
+            tools = ["redis.*"]
+            conditions = [{ pointer = "/unit", regex = "(bad" }]
+            "#,
+        )
+        .unwrap();
+        let compiled = policy.clone().compile();
+
+        let staging = CallerIdentity {
+            client_name: Some("staging-agent".into()),
+            ..Default::default()
+        };
+        let mut sre = CallerIdentity::default();
+        sre.claims.insert("groups".into(), serde_json::json!(["dev", "sre-oncall"]));
+        let names = [
+            "linux.system.cpu",
+            "linux.system.reboot",
+            "linux.service.list",
+            "linux.service.restart",
+            "linux.[bad",
+            "redis.get",
+            "redis.keys",
+            "linux",
+            "linux.file:///var/log/syslog",
+            "linux.runbook-disk",
+            "",
+        ];
+        let arguments = [
+            serde_json::json!({"unit": "nginx.service"}),
+            serde_json::json!({"unit": "sshd.service"}),
+            serde_json::json!({}),
+        ];
+        let restart = compiled.decide_tool("linux.service.restart", &arguments[0], &sre);
+        assert!(restart.matched.iter().any(|m| m.id == "sre-restarts"));
+        for caller in [anyone(), staging, sre] {
+            for name in names {
+                for args in &arguments {
+                    assert_eq!(compiled.decide_tool(name, args, &caller), policy.decide_tool(name, args, &caller), "{name}");
+                }
+                let args = &arguments[0];
+                assert_eq!(compiled.is_tool_visible(name, &caller), policy.is_tool_visible(name, &caller), "{name}");
+                assert_eq!(compiled.decide_resource(name, &caller), policy.decide_resource(name, &caller), "{name}");
+                assert_eq!(compiled.decide_prompt(name, &caller), policy.decide_prompt(name, &caller), "{name}");
+                assert_eq!(compiled.decide_sampling(name, &caller), policy.decide_sampling(name, &caller), "{name}");
+                assert_eq!(
+                    compiled.candidate().unwrap().decide_tool(name, args, &caller),
+                    policy.candidate().unwrap().decide_tool(name, args, &caller),
+                    "{name}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_policy_store_swaps_atomically() {
+        let store = PolicyStore::from(Policy {
+            default_action: Effect::Allow,
+            ..Default::default()
+        });
+        let before = store.load();
+        assert_eq!(store.hash(), None);
+
+        let tightened: Policy = toml::from_str(r#"default_action = "deny""#).unwrap();
+        let hash = content_hash(r#"default_action = "deny""#);
+        assert_eq!(store.clone().swap(tightened, hash.clone()), None);
+
+        // Snapshots taken before the swap keep their rules; new ones see the update
+        assert!(before.is_allowed("linux.system.cpu", &serde_json::json!({}), &anyone()));
+        assert!(!store.load().is_allowed("linux.system.cpu", &serde_json::json!({}), &anyone()));
+        assert_eq!(store.hash(), Some(hash));
+    }
+
+    #[test]
+    fn test_content_hash_is_sha256_hex() {
+        assert_eq!(
+            content_hash(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
     #[test]
     fn test_effect_is_copy() {
         let effect1 = Effect::Allow;
@@ -166,4 +1018,23 @@ mod tests {
         assert_eq!(policy.rules[0].id, "allow-safe");
         assert_eq!(policy.rules[0].tools.len(), 2);
     }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        // A misspelled key would otherwise silently drop a restriction
+        let rule = r#"
+        default_action = "allow"
+
+        [[rules]]
+        id = "protect-sshd"
+        effect = "deny"
+        tools = ["linux.service.restart"]
+        condition = [{ pointer = "/unit", glob = "sshd*" }]
+        "#;
+        let err = toml::from_str::<Policy>(rule).unwrap_err();
+        assert!(err.to_string().contains("unknown field `condition`"), "{}", err);
+
+        let top_level = "default_action = \"deny\"\ndefault_effect = \"allow\"";
+        assert!(toml::from_str::<Policy>(top_level).is_err());
+    }
 }
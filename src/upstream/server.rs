@@ -2,12 +2,20 @@ use rmcp::{
     handler::server::ServerHandler,
     model::*,
     ErrorData as McpError,
-    service::{RequestContext, RoleServer},
+    service::{NotificationContext, Peer, RequestContext, RoleServer, ServiceError},
 };
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use crate::federation::events::FederationEvent;
+use crate::federation::handler::UpstreamContext;
 use crate::federation::manager::FederationManager;
-use crate::security::policy::Policy;
+use crate::federation::namespace;
+use crate::security::policy::{Effect, Policy, PolicyDecision, PolicyStore};
+use crate::security::approval::{ApprovalOutcome, ApprovalQueue};
 use crate::security::audit::AuditLogger;
+use crate::security::identity::{CallerIdentity, IdentityResolver};
 
 /// ProxyEngine is the MCP ServerHandler that neurond exposes upstream (to cortexd).
 ///
@@ -16,56 +24,336 @@ use crate::security::audit::AuditLogger;
 #[derive(Clone)]
 pub struct ProxyEngine {
     federation: Arc<FederationManager>,
-    policy: Arc<Policy>,
+    policy: PolicyStore,
     audit: Arc<AuditLogger>,
+    /// Derives the caller's identity from each request
+    identity: Arc<IdentityResolver>,
+    /// Tool calls held for human approval, shared by all sessions
+    approvals: Arc<ApprovalQueue>,
+    /// Minimum level of downstream log messages relayed to this session;
+    /// None until the client calls `logging/setLevel`.
+    log_level: Arc<Mutex<Option<LoggingLevel>>>,
+    /// Tells this session's calls apart from other sessions' downstream
+    session_id: String,
+    /// Cancelled when the session ends and rmcp drops its engine, which
+    /// stops the session's event relay
+    session: CancellationToken,
+    _session_guard: Arc<DropGuard>,
 }
 
 impl ProxyEngine {
-    pub fn new(federation: Arc<FederationManager>, policy: Arc<Policy>, audit: Arc<AuditLogger>) -> Self {
-        Self { federation, policy, audit }
+    pub fn new(federation: Arc<FederationManager>, policy: PolicyStore, audit: Arc<AuditLogger>) -> Self {
+        let session = CancellationToken::new();
+        Self {
+            federation,
+            policy,
+            audit,
+            identity: Arc::new(IdentityResolver::default()),
+            approvals: Arc::new(ApprovalQueue::default()),
+            log_level: Arc::new(Mutex::new(None)),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            _session_guard: Arc::new(session.clone().drop_guard()),
+            session,
+        }
+    }
+
+    /// Identify callers with `identity` instead of by client name alone.
+    pub fn with_identity(mut self, identity: Arc<IdentityResolver>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Hold `require_approval` calls in `approvals`.
+    pub fn with_approvals(mut self, approvals: Arc<ApprovalQueue>) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    /// Identity of the caller behind `context`: the session's client name plus
+    /// whatever the HTTP request's headers prove.
+    fn caller(&self, context: &RequestContext<RoleServer>) -> CallerIdentity {
+        let client_name = context.peer.peer_info().map(|info| info.client_info.name.as_str());
+        self.identity
+            .resolve(context.extensions.get::<http::request::Parts>(), client_name)
+    }
+
+    /// Aggregated tools, minus those the policy denies `caller` outright.
+    ///
+    /// How many tools each namespace lost is written to the audit log.
+    pub async fn list_visible_tools(&self, caller: &CallerIdentity) -> Vec<Tool> {
+        let policy = self.policy.load();
+        let (tools, hidden): (Vec<Tool>, Vec<Tool>) = self.federation
+            .list_all_tools()
+            .await
+            .into_iter()
+            .partition(|tool| policy.is_tool_visible(&tool.name, caller));
+        if hidden.is_empty() {
+            return tools;
+        }
+
+        let namespaces = self.federation.namespaces().await;
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tool in &hidden {
+            if let Some((ns, _)) = namespace::resolve_namespace(&namespaces, &tool.name) {
+                *counts.entry(ns).or_default() += 1;
+            }
+        }
+        tracing::debug!(hidden = hidden.len(), "Hid tools denied by policy from tools/list");
+        let params = serde_json::json!({ "hidden": counts });
+        let _ = self.audit
+            .for_caller(caller)
+            .log_request("tools/list", "*", &params, "filtered", "success", 0)
+            .await;
+        tools
     }
 
     /// Evaluates tool calls against the configured Policy and Audit log,
     /// before forwarding allowed calls to the federation multiplexer.
-    pub async fn execute_tool_call(&self, request: CallToolRequestParams) -> Result<CallToolResult, McpError> {
+    ///
+    /// `upstream` carries the caller's session, progress token and cancellation
+    /// so they can be relayed to the downstream call.
+    pub async fn execute_tool_call(
+        &self,
+        request: CallToolRequestParams,
+        caller: &CallerIdentity,
+        upstream: Option<UpstreamContext>,
+    ) -> Result<CallToolResult, McpError> {
         let tool_name = request.name.clone();
         let arguments = match request.arguments {
             Some(map) => serde_json::Value::Object(map),
             None => serde_json::json!({}),
         };
 
-        let start = std::time::Instant::now();
+        let (audit, decision) = self
+            .gate("tools/call", &tool_name, &arguments, caller, |policy| {
+                policy.decide_tool(&tool_name, &arguments, caller)
+            })
+            .await;
 
-        if !self.policy.is_allowed(&tool_name) {
-            let _ = self.audit.log(&tool_name, &arguments, "denied", "blocked", 0).await;
-            return Err(McpError {
-                code: ErrorCode::INVALID_REQUEST,
-                message: format!("Access denied to tool {} by security policy", tool_name).into(),
-                data: None,
-            });
-        }
+        let verdict = match decision.effect {
+            Effect::Allow => "allowed",
+            Effect::Deny => {
+                let message = format!("Access denied to tool {} by security policy", tool_name);
+                return Err(refuse(&audit, "tools/call", &tool_name, &arguments, "denied", message, &decision).await);
+            }
+            Effect::RequireApproval => {
+                let outcome = self.approvals
+                    .request(&tool_name, &arguments, caller, upstream.as_ref(), &audit)
+                    .await;
+                let rejection = match outcome {
+                    ApprovalOutcome::Decided(d) if d.approved => None,
+                    ApprovalOutcome::Decided(d) => Some((
+                        "rejected",
+                        format!("rejected by {}{}", d.by, d.reason.map(|r| format!(": {}", r)).unwrap_or_default()),
+                    )),
+                    ApprovalOutcome::Expired => Some(("expired", "not approved in time".to_string())),
+                    ApprovalOutcome::Cancelled => Some(("cancelled", "cancelled while awaiting approval".to_string())),
+                };
+                if let Some((verdict, reason)) = rejection {
+                    let message = format!("Tool call {} {}", tool_name, reason);
+                    return Err(refuse(&audit, "tools/call", &tool_name, &arguments, verdict, message, &decision).await);
+                }
+                "approved"
+            }
+        };
 
         tracing::info!(tool = %tool_name, "Routing tool call to downstream");
+        let call = self.federation.route_tool_call(&tool_name, arguments.clone(), upstream);
+        forward_audited(&audit, "tools/call", &tool_name, &arguments, verdict, call).await
+    }
+
+    /// Evaluates a resource read against the Policy and Audit log,
+    /// before forwarding allowed reads to the owning downstream.
+    pub async fn execute_resource_read(
+        &self,
+        request: ReadResourceRequestParams,
+        caller: &CallerIdentity,
+    ) -> Result<ReadResourceResult, McpError> {
+        let uri = request.uri;
+        let params = serde_json::json!({ "uri": uri });
 
-        let result = self.federation
-            .route_tool_call(&tool_name, arguments.clone())
+        let (audit, decision) = self
+            .gate("resources/read", &uri, &params, caller, |policy| policy.decide_resource(&uri, caller))
             .await;
+        if !decision.is_allowed() {
+            let message = format!("Access denied to resource {} by security policy", uri);
+            return Err(refuse(&audit, "resources/read", &uri, &params, "denied", message, &decision).await);
+        }
 
-        let duration = start.elapsed().as_millis() as u64;
-        let result_str = if result.is_ok() { "success" } else { "error" };
-        
-        self.audit.log(&tool_name, &arguments, "allowed", result_str, duration)
-            .await
-            .map_err(|e| McpError {
-                code: ErrorCode::INTERNAL_ERROR,
-                message: format!("Audit logging failed (disk full?): {}", e).into(),
-                data: None,
-            })?;
+        tracing::info!(uri = %uri, "Routing resource read to downstream");
+        let read = self.federation.read_resource(&uri);
+        forward_audited(&audit, "resources/read", &uri, &params, "allowed", read).await
+    }
+
+    /// Evaluates a prompt fetch against the Policy and Audit log,
+    /// before forwarding allowed requests to the owning downstream.
+    pub async fn execute_prompt_get(
+        &self,
+        request: GetPromptRequestParams,
+        caller: &CallerIdentity,
+    ) -> Result<GetPromptResult, McpError> {
+        let prompt_name = request.name;
+        let arguments = match &request.arguments {
+            Some(map) => serde_json::Value::Object(map.clone()),
+            None => serde_json::json!({}),
+        };
+
+        let (audit, decision) = self
+            .gate("prompts/get", &prompt_name, &arguments, caller, |policy| {
+                policy.decide_prompt(&prompt_name, caller)
+            })
+            .await;
+        if !decision.is_allowed() {
+            let message = format!("Access denied to prompt {} by security policy", prompt_name);
+            return Err(refuse(&audit, "prompts/get", &prompt_name, &arguments, "denied", message, &decision).await);
+        }
+
+        tracing::info!(prompt = %prompt_name, "Routing prompt request to downstream");
+        let get = self.federation.get_prompt(&prompt_name, request.arguments);
+        forward_audited(&audit, "prompts/get", &prompt_name, &arguments, "allowed", get).await
+    }
+
+    /// Decide a request with `decide` under the live policy, auditing any
+    /// disagreement of the candidate policy. Returns the decision and an audit
+    /// logger tagged with the caller and the decision.
+    async fn gate(
+        &self,
+        method: &str,
+        target: &str,
+        params: &serde_json::Value,
+        caller: &CallerIdentity,
+        decide: impl Fn(&Policy) -> PolicyDecision,
+    ) -> (AuditLogger, PolicyDecision) {
+        let audit = self.audit.for_caller(caller);
+        let policy = self.policy.load();
+        let decision = decide(&policy);
+        if let Some(candidate) = policy.candidate() {
+            audit.log_shadow(method, target, params, &decision, &decide(&candidate)).await;
+        }
+        (audit.with_policy(&decision), decision)
+    }
+}
+
+/// Audit a request that was not let through and build the error returned upstream,
+/// which carries the policy decision as its data.
+async fn refuse(
+    audit: &AuditLogger,
+    method: &str,
+    target: &str,
+    params: &serde_json::Value,
+    verdict: &str,
+    message: String,
+    decision: &PolicyDecision,
+) -> McpError {
+    let _ = audit.log_request(method, target, params, verdict, "blocked", 0).await;
+    McpError {
+        code: ErrorCode::INVALID_REQUEST,
+        message: message.into(),
+        data: serde_json::to_value(decision).ok(),
+    }
+}
+
+/// Forward an admitted request and audit its outcome under `verdict`.
+///
+/// Fails the request if the audit record cannot be written.
+async fn forward_audited<T>(
+    audit: &AuditLogger,
+    method: &str,
+    target: &str,
+    params: &serde_json::Value,
+    verdict: &str,
+    forward: impl std::future::Future<Output = Result<T, McpError>>,
+) -> Result<T, McpError> {
+    let start = std::time::Instant::now();
+    let result = forward.await;
+
+    let duration = start.elapsed().as_millis() as u64;
+    let result_str = if result.is_ok() { "success" } else { "error" };
+
+    audit.log_request(method, target, params, verdict, result_str, duration)
+        .await
+        .map_err(|e| McpError {
+            code: ErrorCode::INTERNAL_ERROR,
+            message: format!("Audit logging failed (disk full?): {}", e).into(),
+            data: None,
+        })?;
+
+    result
+}
+
+/// Relay federation change events to one upstream session until it ends.
+async fn forward_events(
+    peer: Peer<RoleServer>,
+    mut events: broadcast::Receiver<FederationEvent>,
+    log_level: Arc<Mutex<Option<LoggingLevel>>>,
+    session: CancellationToken,
+) {
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => event,
+            _ = session.cancelled() => {
+                tracing::debug!("Upstream session ended — stopping event relay");
+                return;
+            }
+        };
+        let sent = match event {
+            Ok(event) => {
+                let min_level = log_level.lock().ok().and_then(|level| *level);
+                relay_event(&peer, &event, min_level).await
+            }
+            // Missed events — the safest catch-up is to invalidate every list
+            Err(RecvError::Lagged(_)) => relay_all_list_changed(&peer).await,
+            Err(RecvError::Closed) => return,
+        };
+        if sent.is_err() {
+            tracing::debug!("Upstream session closed — stopping event relay");
+            return;
+        }
+    }
+}
+
+/// Send the upstream notification matching a federation event.
+///
+/// Log messages are only relayed at or above `min_level`, and not at all without one.
+async fn relay_event(
+    peer: &Peer<RoleServer>,
+    event: &FederationEvent,
+    min_level: Option<LoggingLevel>,
+) -> Result<(), ServiceError> {
+    match event {
+        FederationEvent::ToolListChanged => peer.notify_tool_list_changed().await,
+        FederationEvent::ResourceListChanged => peer.notify_resource_list_changed().await,
+        FederationEvent::PromptListChanged => peer.notify_prompt_list_changed().await,
+        FederationEvent::LogMessage(params) => match min_level {
+            Some(min) if severity(params.level) >= severity(min) => {
+                peer.notify_logging_message(params.clone()).await
+            }
+            _ => Ok(()),
+        },
+    }
+}
 
-        result
+/// Syslog-style ordering of MCP log levels, least severe first.
+fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
     }
 }
 
+/// Tell the session that every list may have changed, after missed events.
+async fn relay_all_list_changed(peer: &Peer<RoleServer>) -> Result<(), ServiceError> {
+    peer.notify_tool_list_changed().await?;
+    peer.notify_resource_list_changed().await?;
+    peer.notify_prompt_list_changed().await
+}
+
 #[allow(clippy::manual_async_fn)]
 impl ServerHandler for ProxyEngine {
     fn get_info(&self) -> ServerInfo {
@@ -79,17 +367,41 @@ impl ServerHandler for ProxyEngine {
                 website_url: None,
             },
             instructions: Some("neurond federation proxy — routes tool calls to downstream MCP servers".to_string()),
+            capabilities: ServerCapabilities::builder()
+                .enable_completions()
+                .enable_logging()
+                .enable_prompts()
+                .enable_prompts_list_changed()
+                .enable_resources()
+                .enable_resources_list_changed()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
             ..Default::default()
         }
     }
 
+    fn on_initialized(
+        &self,
+        context: NotificationContext<RoleServer>,
+    ) -> impl std::future::Future<Output = ()> + Send + '_ {
+        tracing::info!("Upstream session initialized");
+        tokio::spawn(forward_events(
+            context.peer,
+            self.federation.subscribe(),
+            self.log_level.clone(),
+            self.session.clone(),
+        ));
+        std::future::ready(())
+    }
+
     fn list_tools(
         &self,
         _request: Option<PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
-        async {
-            let tools = self.federation.list_all_tools().await;
+        async move {
+            let tools = self.list_visible_tools(&self.caller(&context)).await;
             Ok(ListToolsResult {
                 tools,
                 next_cursor: None,
@@ -101,10 +413,117 @@ impl ServerHandler for ProxyEngine {
     fn call_tool(
         &self,
         request: CallToolRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<CallToolResult, McpError>> + Send + '_ {
         async move {
-            self.execute_tool_call(request).await
+            let caller = self.caller(&context);
+            let upstream = UpstreamContext {
+                progress_token: context.meta.get_progress_token(),
+                peer: context.peer,
+                session_id: self.session_id.clone(),
+                ct: context.ct,
+                policy: self.policy.clone(),
+                audit: Arc::new(self.audit.for_caller(&caller)),
+                caller: caller.clone(),
+            };
+            self.execute_tool_call(request, &caller, Some(upstream)).await
+        }
+    }
+
+    fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ListResourcesResult, McpError>> + Send + '_ {
+        async {
+            let resources = self.federation.list_all_resources().await;
+            Ok(ListResourcesResult {
+                resources,
+                next_cursor: None,
+                meta: None,
+            })
+        }
+    }
+
+    fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ListResourceTemplatesResult, McpError>> + Send + '_ {
+        async {
+            let resource_templates = self.federation.list_all_resource_templates().await;
+            Ok(ListResourceTemplatesResult {
+                resource_templates,
+                next_cursor: None,
+                meta: None,
+            })
+        }
+    }
+
+    fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ListPromptsResult, McpError>> + Send + '_ {
+        async {
+            let prompts = self.federation.list_all_prompts().await;
+            Ok(ListPromptsResult {
+                prompts,
+                next_cursor: None,
+                meta: None,
+            })
+        }
+    }
+
+    fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<GetPromptResult, McpError>> + Send + '_ {
+        async move {
+            self.execute_prompt_get(request, &self.caller(&context)).await
+        }
+    }
+
+    fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ReadResourceResult, McpError>> + Send + '_ {
+        async move {
+            self.execute_resource_read(request, &self.caller(&context)).await
+        }
+    }
+
+    fn set_level(
+        &self,
+        request: SetLevelRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<(), McpError>> + Send + '_ {
+        tracing::debug!(level = ?request.level, "Upstream set downstream log level");
+        if let Ok(mut level) = self.log_level.lock() {
+            *level = Some(request.level);
+        }
+        std::future::ready(Ok(()))
+    }
+
+    fn complete(
+        &self,
+        request: CompleteRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<CompleteResult, McpError>> + Send + '_ {
+        async move {
+            // Don't leak argument values of prompts or resources the caller may not fetch
+            let caller = self.caller(&context);
+            let policy = self.policy.load();
+            let allowed = match &request.r#ref {
+                Reference::Prompt(prompt) => policy.is_prompt_allowed(&prompt.name, &caller),
+                Reference::Resource(resource) => policy.is_resource_allowed(&resource.uri, &caller),
+            };
+            if !allowed {
+                return Ok(CompleteResult::default());
+            }
+            self.federation.complete(request).await
         }
     }
 }
@@ -112,11 +531,14 @@ impl ServerHandler for ProxyEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ApprovalConfig;
+    use crate::federation::catalog::fixture;
+    use crate::security::policy::Policy;
 
     #[tokio::test]
     async fn test_proxy_engine_info() {
         let mgr = Arc::new(FederationManager::new());
-        let policy = Arc::new(Policy::default());
+        let policy = PolicyStore::from(Policy::default());
         let audit = Arc::new(AuditLogger::new("ignore.log"));
         let engine = ProxyEngine::new(mgr, policy, audit);
         let info = engine.get_info();
@@ -126,7 +548,7 @@ mod tests {
     #[tokio::test]
     async fn test_proxy_engine_list_tools_empty() {
         let mgr = Arc::new(FederationManager::new());
-        let policy = Arc::new(Policy::default());
+        let policy = PolicyStore::from(Policy::default());
         let audit = Arc::new(AuditLogger::new("ignore.log"));
         let engine = ProxyEngine::new(mgr, policy, audit);
 
@@ -144,27 +566,170 @@ mod tests {
     #[tokio::test]
     async fn test_proxy_engine_route_unknown_tool() {
         let mgr = Arc::new(FederationManager::new());
-        let result = mgr.route_tool_call("unknown.tool", serde_json::json!({})).await;
+        let result = mgr.route_tool_call("unknown.tool", serde_json::json!({}), None).await;
         assert!(result.is_err());
     }
 
+    /// Upstream test client that reports `tools/list_changed` notifications.
+    struct ListChangedClient(tokio::sync::mpsc::UnboundedSender<()>);
+
+    impl rmcp::ClientHandler for ListChangedClient {
+        async fn on_tool_list_changed(&self, _context: NotificationContext<rmcp::RoleClient>) {
+            let _ = self.0.send(());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_relays_tool_list_changed() {
+        let mgr = Arc::new(FederationManager::new());
+        let policy = PolicyStore::from(Policy::default());
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr.clone(), policy, audit);
+        let session = engine.session.clone();
+
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let server = rmcp::service::serve_server(engine, server_io).await?;
+            server.waiting().await?;
+            anyhow::Ok(())
+        });
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = rmcp::service::serve_client(ListChangedClient(tx), client_io)
+            .await
+            .unwrap();
+
+        // The relay subscribes asynchronously after `initialized`, so re-emit until it lands
+        let relayed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                mgr.notify(FederationEvent::ToolListChanged);
+                let wait = std::time::Duration::from_millis(50);
+                if let Ok(Some(())) = tokio::time::timeout(wait, rx.recv()).await {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(relayed.is_ok(), "upstream should receive tools/list_changed");
+
+        // Ending the session stops its relay without waiting for another event
+        client.cancel().await.unwrap();
+        let stopped = tokio::time::timeout(std::time::Duration::from_secs(5), session.cancelled()).await;
+        assert!(stopped.is_ok(), "relay should stop when the session ends");
+    }
+
+    /// Upstream test client that reports the downstream log messages relayed to it.
+    struct LogClient(tokio::sync::mpsc::UnboundedSender<LoggingMessageNotificationParam>);
+
+    impl rmcp::ClientHandler for LogClient {
+        async fn on_logging_message(
+            &self,
+            params: LoggingMessageNotificationParam,
+            _context: NotificationContext<rmcp::RoleClient>,
+        ) {
+            let _ = self.0.send(params);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_relays_logs_at_or_above_set_level() {
+        let mgr = Arc::new(FederationManager::new());
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr.clone(), PolicyStore::from(Policy::default()), audit);
+
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let server = rmcp::service::serve_server(engine, server_io).await?;
+            server.waiting().await?;
+            anyhow::Ok(())
+        });
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = rmcp::service::serve_client(LogClient(tx), client_io)
+            .await
+            .unwrap();
+        client
+            .peer()
+            .set_level(SetLevelRequestParams {
+                meta: None,
+                level: LoggingLevel::Warning,
+            })
+            .await
+            .unwrap();
+
+        let log = |level, text: &str| {
+            FederationEvent::LogMessage(LoggingMessageNotificationParam {
+                level,
+                logger: Some("linux.mcpd".into()),
+                data: serde_json::json!(text),
+            })
+        };
+        // The relay subscribes asynchronously after `initialized`, so re-emit until it lands
+        let relayed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                mgr.notify(log(LoggingLevel::Info, "chatty"));
+                mgr.notify(log(LoggingLevel::Error, "disk full"));
+                let wait = std::time::Duration::from_millis(50);
+                if let Ok(Some(params)) = tokio::time::timeout(wait, rx.recv()).await {
+                    break params;
+                }
+            }
+        })
+        .await
+        .expect("upstream should receive the error log");
+        assert_eq!(relayed.level, LoggingLevel::Error);
+        assert_eq!(relayed.logger.as_deref(), Some("linux.mcpd"));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_resource_read_policy_enforcement() {
+        let mgr = Arc::new(FederationManager::new());
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(Policy::default()), audit);
+
+        let req = ReadResourceRequestParams {
+            meta: None,
+            uri: "linux.file:///etc/shadow".into(),
+        };
+
+        let err = engine.execute_resource_read(req, &CallerIdentity::default()).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert!(err.message.contains("Access denied to resource"));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_prompt_policy_enforcement() {
+        let mgr = Arc::new(FederationManager::new());
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(Policy::default()), audit);
+
+        let req = GetPromptRequestParams {
+            meta: None,
+            name: "ops.runbook-wipe".into(),
+            arguments: None,
+        };
+
+        let err = engine.execute_prompt_get(req, &CallerIdentity::default()).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert!(err.message.contains("Access denied to prompt"));
+    }
+
     #[tokio::test]
     async fn test_proxy_engine_policy_enforcement() {
         let mgr = Arc::new(FederationManager::new());
 
         // Create a policy that denies "dangerous.tool" but allows others
-        let policy = Policy {
-            default_action: crate::security::policy::Effect::Allow,
-            rules: vec![crate::security::policy::PolicyRule {
-                id: "deny-danger".into(),
-                description: None,
-                effect: crate::security::policy::Effect::Deny,
-                tools: vec!["dangerous.*".into()],
-            }],
-        };
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "allow"
+            [[rules]]
+            id = "deny-danger"
+            effect = "deny"
+            tools = ["dangerous.*"]
+            "#,
+        )
+        .unwrap();
 
         let audit = Arc::new(AuditLogger::new("ignore.log"));
-        let engine = ProxyEngine::new(mgr, Arc::new(policy), audit);
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(policy), audit);
 
         let req = CallToolRequestParams {
             name: "dangerous.tool".into(),
@@ -173,11 +738,343 @@ mod tests {
             task: None,
         };
 
-        let result = engine.execute_tool_call(req).await;
+        let result = engine.execute_tool_call(req, &CallerIdentity::default(), None).await;
         
         // It should be blocked before it even tries to route
         let err = result.unwrap_err();
         assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
         assert!(err.message.contains("Access denied to tool"));
+        // The error explains which rule denied it
+        let data = err.data.unwrap();
+        assert_eq!(data["effect"], "deny");
+        assert_eq!(data["matched"][0]["id"], "deny-danger");
+        assert_eq!(data["matched"][0]["pattern"], "dangerous.*");
+        assert_eq!(data["default_applied"], false);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_policy_sees_call_arguments() {
+        let mgr = Arc::new(FederationManager::new());
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "allow"
+
+            [[rules]]
+            id = "protect-sshd"
+            effect = "deny"
+            tools = ["linux.service.restart"]
+            conditions = [{ pointer = "/unit", glob = "sshd*" }]
+            "#,
+        )
+        .unwrap();
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(policy), audit);
+
+        let restart = |unit: &str| CallToolRequestParams {
+            name: "linux.service.restart".into(),
+            arguments: serde_json::json!({ "unit": unit }).as_object().cloned(),
+            meta: None,
+            task: None,
+        };
+
+        let err = engine.execute_tool_call(restart("sshd.service"), &CallerIdentity::default(), None).await.unwrap_err();
+        assert!(err.message.contains("Access denied to tool"));
+        // Other units pass the policy and fail only for lack of a downstream
+        let err = engine.execute_tool_call(restart("nginx.service"), &CallerIdentity::default(), None).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::METHOD_NOT_FOUND);
+    }
+
+    /// Upstream test client that introduces itself under a given name.
+    struct NamedClient(&'static str);
+
+    impl rmcp::ClientHandler for NamedClient {
+        fn get_info(&self) -> ClientInfo {
+            ClientInfo {
+                client_info: Implementation {
+                    name: self.0.to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_policy_sees_client_name() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "allow"
+
+            [[rules]]
+            id = "no-restarts-from-ide"
+            effect = "deny"
+            tools = ["linux.service.restart"]
+            callers = [{ client_name = "vscode*" }]
+            "#,
+        )
+        .unwrap();
+        let store = PolicyStore::from(policy);
+
+        for (client_name, denied) in [("vscode-mcp", true), ("cortexd", false)] {
+            let mgr = Arc::new(FederationManager::new());
+            let audit = Arc::new(AuditLogger::new("ignore.log"));
+            let engine = ProxyEngine::new(mgr, store.clone(), audit);
+            let (client_io, server_io) = tokio::io::duplex(4096);
+            tokio::spawn(async move {
+                let server = rmcp::service::serve_server(engine, server_io).await?;
+                server.waiting().await?;
+                anyhow::Ok(())
+            });
+            let client = rmcp::service::serve_client(NamedClient(client_name), client_io)
+                .await
+                .unwrap();
+
+            let err = client
+                .peer()
+                .call_tool(CallToolRequestParams {
+                    name: "linux.service.restart".into(),
+                    arguments: None,
+                    meta: None,
+                    task: None,
+                })
+                .await
+                .unwrap_err();
+            let ServiceError::McpError(err) = err else {
+                panic!("unexpected error: {err:?}");
+            };
+            assert_eq!(err.message.contains("Access denied to tool"), denied, "{client_name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_hides_completions_of_denied_references() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+
+            [[rules]]
+            id = "logs"
+            effect = "allow"
+            resources = ["linux.file:///var/log/*"]
+            "#,
+        )
+        .unwrap();
+        let mgr = Arc::new(FederationManager::new());
+        let audit = Arc::new(AuditLogger::new("ignore.log"));
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(policy), audit);
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let server = rmcp::service::serve_server(engine, server_io).await?;
+            server.waiting().await?;
+            anyhow::Ok(())
+        });
+        let client = rmcp::service::serve_client((), client_io).await.unwrap();
+        let complete = |r#ref: Reference| CompleteRequestParams {
+            meta: None,
+            r#ref,
+            argument: ArgumentInfo {
+                name: "name".into(),
+                value: "s".into(),
+            },
+            context: None,
+        };
+
+        for r#ref in [
+            Reference::Resource(ResourceReference { uri: "linux.file:///etc/{name}".into() }),
+            Reference::Prompt(PromptReference { name: "linux.runbook".into(), title: None }),
+        ] {
+            let result = client.peer().complete(complete(r#ref)).await.unwrap();
+            assert!(result.completion.values.is_empty());
+        }
+
+        // An allowed template is forwarded, and fails only for lack of a downstream
+        let allowed = Reference::Resource(ResourceReference { uri: "linux.file:///var/log/{name}".into() });
+        assert!(client.peer().complete(complete(allowed)).await.is_err());
+    }
+
+    /// Upstream test client that answers approval elicitations with a fixed verdict.
+    struct ConfirmingClient(bool);
+
+    impl rmcp::ClientHandler for ConfirmingClient {
+        fn get_info(&self) -> ClientInfo {
+            ClientInfo {
+                capabilities: ClientCapabilities::builder().enable_elicitation().build(),
+                ..Default::default()
+            }
+        }
+
+        async fn create_elicitation(
+            &self,
+            _request: CreateElicitationRequestParams,
+            _context: RequestContext<rmcp::RoleClient>,
+        ) -> Result<CreateElicitationResult, McpError> {
+            Ok(CreateElicitationResult {
+                action: ElicitationAction::Accept,
+                content: Some(serde_json::json!({ "approve": self.0 })),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_asks_upstream_to_approve() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "allow"
+
+            [[rules]]
+            id = "approve-restarts"
+            effect = "require_approval"
+            tools = ["linux.service.restart"]
+            "#,
+        )
+        .unwrap();
+        let store = PolicyStore::from(policy);
+
+        for approve in [true, false] {
+            let mgr = Arc::new(FederationManager::new());
+            let audit = Arc::new(AuditLogger::new("ignore.log"));
+            let approvals = ApprovalQueue::new(&ApprovalConfig {
+                elicit: true,
+                ..Default::default()
+            });
+            let engine = ProxyEngine::new(mgr, store.clone(), audit).with_approvals(Arc::new(approvals));
+            let (client_io, server_io) = tokio::io::duplex(4096);
+            tokio::spawn(async move {
+                let server = rmcp::service::serve_server(engine, server_io).await?;
+                server.waiting().await?;
+                anyhow::Ok(())
+            });
+            let client = rmcp::service::serve_client(ConfirmingClient(approve), client_io)
+                .await
+                .unwrap();
+
+            let err = client
+                .peer()
+                .call_tool(CallToolRequestParams {
+                    name: "linux.service.restart".into(),
+                    arguments: None,
+                    meta: None,
+                    task: None,
+                })
+                .await
+                .unwrap_err();
+            let ServiceError::McpError(err) = err else {
+                panic!("unexpected error: {err:?}");
+            };
+            if approve {
+                // Approved calls proceed and fail only for lack of a downstream
+                assert_eq!(err.code, ErrorCode::METHOD_NOT_FOUND);
+            } else {
+                assert!(err.message.contains("rejected by elicitation"), "{}", err.message);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_hides_denied_tools() {
+        let dir = std::env::temp_dir().join(format!("neurond-list-{}", uuid::Uuid::new_v4()));
+        let catalogs: [(&str, &[&str]); 2] = [
+            ("linux", &["linux.system.cpu", "linux.shell.exec", "linux.service.restart"]),
+            ("redis", &["redis.get", "redis.flushall"]),
+        ];
+        fixture::save_catalogs(&dir, &catalogs);
+        let federation = fixture::lazy_config(&dir, &[("linux", &[]), ("redis", &[])]);
+        let mgr = Arc::new(FederationManager::new());
+        mgr.init_from_config(&federation).await.unwrap();
+
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "allow"
+
+            [[rules]]
+            id = "no-shell"
+            effect = "deny"
+            tools = ["linux.shell.*", "redis.flushall"]
+
+            [[rules]]
+            id = "protect-sshd"
+            effect = "deny"
+            tools = ["linux.service.restart"]
+            conditions = [{ pointer = "/unit", glob = "sshd*" }]
+            "#,
+        )
+        .unwrap();
+        let audit_path = dir.join("audit.log");
+        let audit = Arc::new(AuditLogger::new(audit_path.to_str().unwrap()));
+        let engine = ProxyEngine::new(mgr, PolicyStore::from(policy), audit);
+
+        let names: Vec<String> = engine
+            .list_visible_tools(&CallerIdentity::default())
+            .await
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["linux.system.cpu", "linux.service.restart", "redis.get"]);
+
+        let event: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&audit_path).unwrap().trim()).unwrap();
+        assert_eq!(event["method"], "tools/list");
+        assert_eq!(event["params"]["hidden"], serde_json::json!({"linux": 1, "redis": 1}));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_engine_audits_shadow_disagreements() {
+        let policy: Policy = toml::from_str(
+            r#"
+            default_action = "deny"
+
+            [[rules]]
+            id = "allow-linux"
+            effect = "allow"
+            tools = ["linux.*"]
+
+            [[rules]]
+            id = "tighten-restarts"
+            effect = "deny"
+            mode = "audit"
+            tools = ["linux.service.restart"]
+
+            [[rules]]
+            id = "open-redis"
+            effect = "allow"
+            mode = "audit"
+            tools = ["redis.get"]
+            "#,
+        )
+        .unwrap();
+        let audit_path = std::env::temp_dir().join(format!("neurond-shadow-{}.log", uuid::Uuid::new_v4()));
+        let audit = Arc::new(AuditLogger::new(audit_path.to_str().unwrap()));
+        let engine = ProxyEngine::new(Arc::new(FederationManager::new()), PolicyStore::from(policy), audit);
+        let call = |name: &str| CallToolRequestParams {
+            name: name.to_string().into(),
+            arguments: None,
+            meta: None,
+            task: None,
+        };
+        let caller = CallerIdentity::default();
+
+        // The live policy still decides: restarts pass (and fail for lack of a downstream)
+        let err = engine.execute_tool_call(call("linux.service.restart"), &caller, None).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::METHOD_NOT_FOUND);
+        let err = engine.execute_tool_call(call("redis.get"), &caller, None).await.unwrap_err();
+        assert!(err.message.contains("Access denied to tool"));
+        // Agreement is not recorded
+        let _ = engine.execute_tool_call(call("linux.system.cpu"), &caller, None).await;
+
+        let log = std::fs::read_to_string(&audit_path).unwrap();
+        let shadow: Vec<serde_json::Value> = log
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .filter(|e| e["method"] == "policy/shadow")
+            .collect();
+        assert_eq!(shadow.len(), 2);
+        assert_eq!(shadow[0]["tool"], "linux.service.restart");
+        assert_eq!(shadow[0]["decision"], "would_deny");
+        assert_eq!(shadow[0]["params"]["enforced"]["effect"], "allow");
+        assert_eq!(shadow[0]["params"]["candidate"]["matched"][1]["id"], "tighten-restarts");
+        assert_eq!(shadow[1]["tool"], "redis.get");
+        assert_eq!(shadow[1]["decision"], "would_allow");
+        let _ = std::fs::remove_file(audit_path);
     }
 }